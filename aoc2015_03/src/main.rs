@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::io::*;
 use std::fs::*;
@@ -13,6 +13,42 @@ fn move_santa(pos: (i32, i32), dir: char) -> (i32, i32) {
     }
 }
 
+struct Loop {
+    house: (i32, i32),
+    start: usize,
+    end: usize,
+}
+
+impl Loop {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+struct RouteAnalysis {
+    first_revisit: Option<(usize, (i32, i32))>,
+    loops: Vec<Loop>,
+    max_distance: i32,
+}
+
+// Steps are indices into the visited houses, step 0 being the starting house.
+fn analyse_route(visited: &[(i32, i32)]) -> RouteAnalysis {
+    let mut last_visit: HashMap<(i32, i32), usize> = HashMap::new();
+    let mut loops = Vec::new();
+    let mut max_distance = 0;
+
+    for (step, &house) in visited.iter().enumerate() {
+        if let Some(start) = last_visit.insert(house, step) {
+            loops.push(Loop { house, start, end: step });
+        }
+        max_distance = max_distance.max(house.0.abs() + house.1.abs());
+    }
+
+    let first_revisit = loops.first().map(|l| (l.end, l.house));
+
+    RouteAnalysis { first_revisit, loops, max_distance }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     assert!((args.len() > 1), "Missing input file argument");
@@ -30,6 +66,15 @@ fn main() {
     let visited_unique = visited_house_coords.iter().collect::<HashSet<_>>().len();
     println!("Silver: {}", visited_unique);
 
+    let analysis = analyse_route(&visited_house_coords);
+    match analysis.first_revisit {
+        Some((step, house)) => println!("First revisit: house {:?} at step {}", house, step),
+        None => println!("First revisit: none"),
+    }
+    let longest_loop = analysis.loops.iter().map(Loop::len).max().unwrap_or(0);
+    println!("Loops: {} (longest {} steps)", analysis.loops.len(), longest_loop);
+    println!("Max distance: {}", analysis.max_distance);
+
     let visited_coords_santa: Vec<(i32, i32)> = data.chars().step_by(2).fold(vec![(0,0)], |mut visited, c| {
         let (x, y) = visited.iter().last().unwrap();
         visited.push(move_santa((*x, *y), c));
@@ -45,3 +90,42 @@ fn main() {
     let visited_unique_2 = visited_coords_santa.iter().chain(visited_coords_robot.iter()).collect::<HashSet<_>>().len();
     println!("Gold: {}", visited_unique_2);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visit(moves: &str) -> Vec<(i32, i32)> {
+        moves.chars().fold(vec![(0,0)], |mut visited, c| {
+            let last = *visited.last().unwrap();
+            visited.push(move_santa(last, c));
+            visited
+        })
+    }
+
+    #[test]
+    fn test_no_revisit() {
+        let analysis = analyse_route(&visit(">>^^"));
+        assert!(analysis.first_revisit.is_none());
+        assert!(analysis.loops.is_empty());
+        assert_eq!(analysis.max_distance, 4);
+    }
+
+    #[test]
+    fn test_square_loop() {
+        let analysis = analyse_route(&visit("^>v<"));
+        assert_eq!(analysis.first_revisit, Some((4, (0, 0))));
+        assert_eq!(analysis.loops.len(), 1);
+        assert_eq!(analysis.loops[0].len(), 4);
+        assert_eq!(analysis.max_distance, 2);
+    }
+
+    #[test]
+    fn test_back_and_forth() {
+        let analysis = analyse_route(&visit("^v^v^v^v^v"));
+        assert_eq!(analysis.first_revisit, Some((2, (0, 0))));
+        assert_eq!(analysis.loops.len(), 9);
+        assert!(analysis.loops.iter().all(|l| l.len() == 2));
+        assert_eq!(analysis.max_distance, 1);
+    }
+}
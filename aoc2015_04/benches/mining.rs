@@ -0,0 +1,27 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc2015_04::pow::{brute_force_with, Md5, Sha1, Sha256};
+use aoc2015_04::*;
+
+fn mining(c: &mut Criterion) {
+    let mut group = c.benchmark_group("five_zeros");
+    group.sample_size(10);
+    group.bench_function("brute_force", |b| b.iter(|| brute_force("abcdef", "00000")));
+    group.bench_function("brute_force_optim", |b| b.iter(|| brute_force_optim("abcdef", &FIVE_ZEROS)));
+    group.bench_function("brute_force_midstate", |b| b.iter(|| brute_force_midstate("abcdef", &FIVE_ZEROS)));
+    group.bench_function("brute_force_lanes", |b| b.iter(|| brute_force_lanes("abcdef", &FIVE_ZEROS)));
+    group.finish();
+}
+
+fn hash_rates(c: &mut Criterion) {
+    let difficulty = Difficulty::zero_nibbles(4);
+    let mut group = c.benchmark_group("four_zeros");
+    group.sample_size(10);
+    group.bench_function("md5", |b| b.iter(|| brute_force_with::<Md5>("abcdef", &difficulty)));
+    group.bench_function("sha1", |b| b.iter(|| brute_force_with::<Sha1>("abcdef", &difficulty)));
+    group.bench_function("sha256", |b| b.iter(|| brute_force_with::<Sha256>("abcdef", &difficulty)));
+    group.finish();
+}
+
+criterion_group!(benches, mining, hash_rates);
+criterion_main!(benches);
@@ -0,0 +1,308 @@
+pub mod md5_lanes;
+pub mod pow;
+pub mod resume;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use pow::ProofOfWorkHash;
+
+pub const FIVE_ZEROS: Difficulty = Difficulty::zero_nibbles(5);
+pub const SIX_ZEROS: Difficulty = Difficulty::zero_nibbles(6);
+const PARALLEL_BATCH_SIZE: u32 = 4096;
+
+// The digest is read big-endian so that its leading hex digits are the most
+// significant bits, whatever the platform endianness.
+pub struct Difficulty {
+    mask: u128,
+    target: u128,
+}
+
+impl Difficulty
+{
+    pub const fn zero_bits(bits: u32) -> Difficulty {
+        assert!(bits <= 128, "Difficulty only covers the first 128 bits of a digest");
+        let mask = if bits == 0 { 0 } else { u128::MAX << (128 - bits) };
+        Difficulty { mask, target: 0 }
+    }
+
+    pub const fn zero_nibbles(nibbles: u32) -> Difficulty {
+        Difficulty::zero_bits(nibbles * 4)
+    }
+
+    pub fn hex_prefix(prefix: &str) -> Option<Difficulty> {
+        if prefix.len() > 32 {
+            return None;
+        }
+        let mut target = 0u128;
+        for c in prefix.chars() {
+            target = (target << 4) | c.to_digit(16)? as u128;
+        }
+        let bits = prefix.len() as u32 * 4;
+        let mask = Difficulty::zero_bits(bits).mask;
+        let target = if bits == 0 { 0 } else { target << (128 - bits) };
+        Some(Difficulty { mask, target })
+    }
+
+    pub fn is_met(&self, digest: &[u8; 16]) -> bool {
+        u128::from_be_bytes(*digest) & self.mask == self.target
+    }
+
+    pub fn expected_attempts(&self) -> f64 {
+        2f64.powi(self.mask.count_ones() as i32)
+    }
+}
+
+pub fn brute_force(secret: &str, prefix: &str) -> u32 
+{
+    let mut i = 0;
+    loop {
+        let digest = md5::compute(format!("{}{}", secret, i));
+        let hex = format!("{:x}", digest);
+        if hex.starts_with(prefix) {
+            return i;
+        }
+        i += 1;
+    }
+}
+
+fn is_valid_nonce(secret: &str, difficulty: &Difficulty, i: u32) -> bool
+{
+    let i_string = i.to_string();
+    let mut data = String::with_capacity(secret.len() + i_string.len());
+    data.push_str(secret);
+    data.push_str(i_string.as_str());
+
+    let digest = md5::compute(data);
+    difficulty.is_met(&digest.0)
+}
+
+pub fn brute_force_optim(secret: &str, difficulty: &Difficulty) -> u32
+{
+    let mut i = 0;    
+    loop {
+        if is_valid_nonce(secret, difficulty, i) {
+            return i;
+        }
+        i += 1;
+    }
+}
+
+pub fn brute_force_parallel(secret: &str, difficulty: &Difficulty, threads: usize) -> u32
+{
+    brute_force_parallel_with::<pow::Md5>(secret, difficulty, threads)
+}
+
+// Worker w searches batches w, w + threads, w + 2 * threads, ... and stops once
+// its next batch starts past the best nonce found so far. Every batch below the
+// best nonce is fully searched, so the result matches brute_force_optim.
+pub fn brute_force_parallel_with<H: ProofOfWorkHash>(secret: &str, difficulty: &Difficulty, threads: usize) -> u32
+{
+    let threads = threads.max(1) as u32;
+    let best = AtomicU32::new(u32::MAX);
+
+    thread::scope(|scope| {
+        for worker in 0..threads {
+            let best = &best;
+            scope.spawn(move || {
+                let prefix = H::absorb(secret.as_bytes());
+                let mut batch = worker;
+                loop {
+                    let Some(start) = batch.checked_mul(PARALLEL_BATCH_SIZE) else { return };
+                    if start >= best.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let end = start.saturating_add(PARALLEL_BATCH_SIZE);
+                    let mut nonce = DecimalCounter::starting_at(start);
+                    let found = (start..end).find(|_| {
+                        let valid = difficulty.is_met(&H::finish(prefix.clone(), nonce.as_bytes()));
+                        nonce.increment();
+                        valid
+                    });
+                    if let Some(i) = found {
+                        best.fetch_min(i, Ordering::Relaxed);
+                        return;
+                    }
+                    batch += threads;
+                }
+            });
+        }
+    });
+
+    best.into_inner()
+}
+
+struct DecimalCounter {
+    digits: [u8; 10],
+    start: usize,
+}
+
+impl DecimalCounter
+{
+    fn new() -> DecimalCounter {
+        DecimalCounter { digits: [b'0'; 10], start: 9 }
+    }
+
+    fn starting_at(value: u32) -> DecimalCounter {
+        let mut counter = DecimalCounter::new();
+        let text = value.to_string();
+        counter.start = counter.digits.len() - text.len();
+        counter.digits[counter.start..].copy_from_slice(text.as_bytes());
+        counter
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.digits[self.start..]
+    }
+
+    fn increment(&mut self) {
+        let mut pos = self.digits.len();
+        while pos > 0 {
+            pos -= 1;
+            if self.digits[pos] == b'9' {
+                self.digits[pos] = b'0';
+            } else {
+                self.digits[pos] += 1;
+                self.start = self.start.min(pos);
+                return;
+            }
+        }
+        panic!("Nonce space exhausted");
+    }
+}
+
+// The secret is absorbed once into an MD5 context; each attempt clones that
+// midstate and only hashes the nonce digits, kept in a stack buffer.
+pub fn brute_force_midstate(secret: &str, difficulty: &Difficulty) -> u32
+{
+    pow::brute_force_with::<pow::Md5>(secret, difficulty)
+}
+
+// Hashes eight consecutive nonces per call to the multi-lane MD5.
+pub fn brute_force_lanes(secret: &str, difficulty: &Difficulty) -> u32
+{
+    let mut nonce = DecimalCounter::new();
+    let mut messages: [Vec<u8>; 8] = Default::default();
+    let mut i = 0;
+    loop {
+        for message in messages.iter_mut() {
+            message.clear();
+            message.extend_from_slice(secret.as_bytes());
+            message.extend_from_slice(nonce.as_bytes());
+            nonce.increment();
+        }
+        let refs: [&[u8]; 8] = std::array::from_fn(|lane| messages[lane].as_slice());
+        if let Some(lane) = md5_lanes::digest_x8(&refs).iter().position(|d| difficulty.is_met(d)) {
+            return i + lane as u32;
+        }
+        i += 8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brute_force1() {
+        assert_eq!(brute_force("abcdef", "00000"), 609043);
+    }
+
+    #[test]
+    fn test_brute_force2() {
+        assert_eq!(brute_force("pqrstuv", "00000"), 1048970);
+    }
+
+    #[test]
+    fn test_brute_force_optim1() {
+        assert_eq!(brute_force_optim("abcdef", &FIVE_ZEROS), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_optim2() {
+        assert_eq!(brute_force_optim("pqrstuv", &FIVE_ZEROS), 1048970);
+    }
+
+    #[test]
+    fn test_brute_force_parallel1() {
+        assert_eq!(brute_force_parallel("abcdef", &FIVE_ZEROS, 4), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_parallel2() {
+        assert_eq!(brute_force_parallel("pqrstuv", &FIVE_ZEROS, 3), 1048970);
+    }
+
+    #[test]
+    fn test_brute_force_parallel_single_thread() {
+        assert_eq!(brute_force_parallel("abcdef", &FIVE_ZEROS, 1), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_parallel_with_sha1() {
+        assert_eq!(brute_force_parallel_with::<pow::Sha1>("abcdef", &Difficulty::zero_nibbles(4), 3), 185343);
+    }
+
+    #[test]
+    fn test_difficulty_zero_nibbles() {
+        let digest = md5::compute("abcdef609043").0;
+        assert!(FIVE_ZEROS.is_met(&digest));
+        assert!(!SIX_ZEROS.is_met(&digest));
+    }
+
+    #[test]
+    fn test_difficulty_zero_bits() {
+        assert!(Difficulty::zero_bits(0).is_met(&[0xFF; 16]));
+        let mut digest = [0u8; 16];
+        digest[2] = 0x10;
+        assert!(Difficulty::zero_bits(19).is_met(&digest));
+        assert!(!Difficulty::zero_bits(20).is_met(&digest));
+        assert!(Difficulty::zero_bits(128).is_met(&[0; 16]));
+    }
+
+    #[test]
+    fn test_difficulty_hex_prefix() {
+        let mut digest = [0u8; 16];
+        digest[0] = 0xAB;
+        digest[1] = 0xC4;
+        assert!(Difficulty::hex_prefix("abc").unwrap().is_met(&digest));
+        assert!(Difficulty::hex_prefix("ABC4").unwrap().is_met(&digest));
+        assert!(!Difficulty::hex_prefix("abd").unwrap().is_met(&digest));
+        assert!(Difficulty::hex_prefix("xyz").is_none());
+    }
+
+    #[test]
+    fn test_brute_force_optim_hex_prefix() {
+        let difficulty = Difficulty::hex_prefix("00000").unwrap();
+        assert_eq!(brute_force_optim("abcdef", &difficulty), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_midstate1() {
+        assert_eq!(brute_force_midstate("abcdef", &FIVE_ZEROS), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_midstate2() {
+        assert_eq!(brute_force_midstate("pqrstuv", &FIVE_ZEROS), 1048970);
+    }
+
+    #[test]
+    fn test_decimal_counter() {
+        let mut counter = DecimalCounter::new();
+        for i in 0..100_000u32 {
+            assert_eq!(counter.as_bytes(), i.to_string().as_bytes());
+            counter.increment();
+        }
+    }
+
+    #[test]
+    fn test_brute_force_lanes1() {
+        assert_eq!(brute_force_lanes("abcdef", &FIVE_ZEROS), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_lanes2() {
+        assert_eq!(brute_force_lanes("pqrstuv", &FIVE_ZEROS), 1048970);
+    }
+}
@@ -1,71 +1,130 @@
-const FIVE_ZEROS_MASK: u128 = u128::from_ne_bytes([0xFF, 0xFF, 0xF0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
-const SIX_ZEROS_MASK: u128 = u128::from_ne_bytes([0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+use std::env;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
 
-#[allow(dead_code)]
-fn brute_force(secret: &str, prefix: &str) -> u32 
+use aoc2015_04::pow::{self, ProofOfWorkHash};
+use aoc2015_04::resume::{search_range, Checkpoint, Progress};
+use aoc2015_04::*;
+
+const REPORT_INTERVAL: u32 = 1 << 20;
+
+fn arg_value(name: &str) -> Option<String>
 {
-    let mut i = 0;
-    loop {
-        let digest = md5::compute(format!("{}{}", secret, i));
-        let hex = format!("{:x}", digest);
-        if hex.starts_with(prefix) {
-            return i;
-        }
-        i += 1;
-    }
+    let args: Vec<String> = env::args().collect();
+    let idx = args.iter().position(|a| a == name)?;
+    let value = args.get(idx + 1).unwrap_or_else(|| panic!("{} expects a value", name));
+    Some(value.clone())
 }
 
-fn brute_force_optim(secret: &str, mask: &u128) -> u32
+fn thread_count() -> usize
 {
-    let mut i = 0;    
-    loop {
-        let i_string = i.to_string();
-        let mut data = String::with_capacity(secret.len() + i_string.len());
-        data.push_str(secret);
-        data.push_str(i_string.as_str());
-
-        let digest = md5::compute(data);
-        let dg = u128::from_ne_bytes(digest.0);
-        
-        if dg & mask == 0 {
-            return i;
-        }
-        i += 1;
+    match arg_value("--threads") {
+        Some(n) => n.parse::<usize>().expect("--threads expects a positive number"),
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
     }
 }
 
-fn main() {
-    let secret = "yzbqklnj";
+fn custom_difficulty() -> Option<Difficulty>
+{
+    if let Some(n) = arg_value("--zeros") {
+        return Some(Difficulty::zero_nibbles(n.parse().expect("--zeros expects a number")));
+    }
+    if let Some(n) = arg_value("--bits") {
+        return Some(Difficulty::zero_bits(n.parse().expect("--bits expects a number")));
+    }
+    arg_value("--prefix").map(|p| Difficulty::hex_prefix(&p).expect("--prefix expects up to 32 hex digits"))
+}
 
-    let silver = brute_force_optim(secret, &FIVE_ZEROS_MASK);
-    println!("Silver = {}", silver);
-    
-    let gold = brute_force_optim(secret, &SIX_ZEROS_MASK);
-    println!("Gold = {}", gold);
+fn print_progress(progress: &Progress)
+{
+    let eta = progress.eta().map_or("unknown".to_string(), |eta| format!("{}s", eta.as_secs()));
+    eprintln!("nonce {} | {:.0} nonces/s | ETA {}", progress.next, progress.rate(), eta);
 }
 
+// Searches --start..--end, resuming from and periodically updating --checkpoint.
+fn resumable_search<H: ProofOfWorkHash>(secret: &str)
+{
+    let difficulty = custom_difficulty().unwrap_or(SIX_ZEROS);
+    let checkpoint_path = arg_value("--checkpoint").map(PathBuf::from);
+    let mut start = arg_value("--start").map_or(0, |n| n.parse::<u32>().expect("--start expects a nonce"));
+    let end = arg_value("--end").map_or(u32::MAX, |n| n.parse::<u32>().expect("--end expects a nonce"));
+
+    if let Some(path) = checkpoint_path.as_ref().filter(|p| p.exists()) {
+        let checkpoint = Checkpoint::load(path).expect("Failed to read checkpoint");
+        assert!(checkpoint.matches(secret, H::NAME, &difficulty), "Checkpoint belongs to another search");
+        start = start.max(checkpoint.next);
+        eprintln!("Resuming from nonce {}", start);
+    }
+
+    let found = search_range::<H, _>(secret, &difficulty, start..end, REPORT_INTERVAL, |progress| {
+        print_progress(progress);
+        if let Some(path) = &checkpoint_path {
+            Checkpoint::new(secret, H::NAME, &difficulty, progress.next).save(path).expect("Failed to write checkpoint");
+        }
+    });
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    match found {
+        Some(nonce) => println!("Found = {}", nonce),
+        None => println!("No valid nonce in {}..{}", start, end),
+    }
+}
+
+fn mine_with<H: ProofOfWorkHash>(secret: &str, difficulty: &Difficulty, threads: usize)
+{
+    let started = Instant::now();
+    let nonce = brute_force_parallel_with::<H>(secret, difficulty, threads);
+    let elapsed = started.elapsed().as_secs_f64();
+    println!("{} = {} ({:.0} hashes/s)", H::NAME, nonce, (nonce as f64 + 1.0) / elapsed);
+}
 
-    #[test]
-    fn test_brute_force1() {
-        assert_eq!(brute_force("abcdef", "00000"), 609043);
+fn take_with<H: ProofOfWorkHash>(secret: &str, difficulty: &Difficulty, count: usize)
+{
+    let nonces: Vec<u32> = pow::valid_nonces::<H>(secret, difficulty).take(count).collect();
+    for nonce in &nonces {
+        println!("{}", nonce);
     }
+    let stats = pow::spacing(&nonces, difficulty);
+    println!("{} hits, spacing mean {:.1} (expected {:.1}), min {}, max {}",
+        stats.hits, stats.mean, stats.expected, stats.min, stats.max);
+}
 
-    #[test]
-    fn test_brute_force2() {
-        assert_eq!(brute_force("pqrstuv", "00000"), 1048970);
+// Every mode mines with the hash function chosen by --hash (MD5 by default).
+fn run<H: ProofOfWorkHash>(secret: &str, hash_chosen: bool)
+{
+    if ["--start", "--end", "--checkpoint"].iter().any(|a| arg_value(a).is_some()) {
+        resumable_search::<H>(secret);
+        return;
+    }
+    if let Some(count) = arg_value("--take") {
+        let count = count.parse::<usize>().expect("--take expects a count");
+        take_with::<H>(secret, &custom_difficulty().unwrap_or(FIVE_ZEROS), count);
+        return;
     }
+    let threads = thread_count();
 
-    #[test]
-    fn test_brute_force_optim1() {
-        assert_eq!(brute_force_optim("abcdef", &FIVE_ZEROS_MASK), 609043);
+    let silver = brute_force_parallel_with::<H>(secret, &FIVE_ZEROS, threads);
+    println!("Silver = {}", silver);
+    
+    let gold = brute_force_parallel_with::<H>(secret, &SIX_ZEROS, threads);
+    println!("Gold = {}", gold);
+
+    if let Some(difficulty) = custom_difficulty() {
+        if hash_chosen {
+            mine_with::<H>(secret, &difficulty, threads);
+        } else {
+            println!("Custom = {}", brute_force_parallel_with::<H>(secret, &difficulty, threads));
+        }
     }
+}
 
-    #[test]
-    fn test_brute_force_optim2() {
-        assert_eq!(brute_force_optim("pqrstuv", &FIVE_ZEROS_MASK), 1048970);
+fn main() {
+    let secret = "yzbqklnj";
+    let hash = arg_value("--hash");
+    match hash.as_deref().unwrap_or(pow::Md5::NAME) {
+        pow::Md5::NAME => run::<pow::Md5>(secret, hash.is_some()),
+        pow::Sha1::NAME => run::<pow::Sha1>(secret, hash.is_some()),
+        pow::Sha256::NAME => run::<pow::Sha256>(secret, hash.is_some()),
+        other => panic!("Unknown hash function: {}", other),
     }
-}
\ No newline at end of file
+}
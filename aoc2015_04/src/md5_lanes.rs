@@ -0,0 +1,322 @@
+// MD5 (RFC 1321) computed over several messages at once, one message per lane.
+// The round function is written once against the Lanes trait; x86_64 uses SSE2
+// registers, every other target falls back to plain u32 arrays.
+
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const SINES: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+pub trait Lanes: Copy {
+    const WIDTH: usize;
+
+    fn splat(v: u32) -> Self;
+    fn from_words(words: &[u32]) -> Self;
+    fn to_words(self, words: &mut [u32]);
+    fn add(self, other: Self) -> Self;
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    fn xor(self, other: Self) -> Self;
+    fn rotate_left(self, n: u32) -> Self;
+
+    fn not(self) -> Self {
+        self.xor(Self::splat(u32::MAX))
+    }
+}
+
+impl<const N: usize> Lanes for [u32; N] {
+    const WIDTH: usize = N;
+
+    fn splat(v: u32) -> Self {
+        [v; N]
+    }
+
+    fn from_words(words: &[u32]) -> Self {
+        std::array::from_fn(|i| words[i])
+    }
+
+    fn to_words(self, words: &mut [u32]) {
+        words[..N].copy_from_slice(&self);
+    }
+
+    fn add(self, other: Self) -> Self {
+        std::array::from_fn(|i| self[i].wrapping_add(other[i]))
+    }
+
+    fn and(self, other: Self) -> Self {
+        std::array::from_fn(|i| self[i] & other[i])
+    }
+
+    fn or(self, other: Self) -> Self {
+        std::array::from_fn(|i| self[i] | other[i])
+    }
+
+    fn xor(self, other: Self) -> Self {
+        std::array::from_fn(|i| self[i] ^ other[i])
+    }
+
+    fn rotate_left(self, n: u32) -> Self {
+        std::array::from_fn(|i| self[i].rotate_left(n))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use super::Lanes;
+    use std::arch::x86_64::*;
+
+    // SSE2 is part of the x86_64 baseline, so these intrinsics are always available.
+    #[derive(Clone, Copy)]
+    pub struct Sse2(__m128i);
+
+    impl Lanes for Sse2 {
+        const WIDTH: usize = 4;
+
+        fn splat(v: u32) -> Self {
+            unsafe { Sse2(_mm_set1_epi32(v as i32)) }
+        }
+
+        fn from_words(words: &[u32]) -> Self {
+            unsafe { Sse2(_mm_setr_epi32(words[0] as i32, words[1] as i32, words[2] as i32, words[3] as i32)) }
+        }
+
+        fn to_words(self, words: &mut [u32]) {
+            let mut out = [0u32; 4];
+            unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, self.0) };
+            words[..4].copy_from_slice(&out);
+        }
+
+        fn add(self, other: Self) -> Self {
+            unsafe { Sse2(_mm_add_epi32(self.0, other.0)) }
+        }
+
+        fn and(self, other: Self) -> Self {
+            unsafe { Sse2(_mm_and_si128(self.0, other.0)) }
+        }
+
+        fn or(self, other: Self) -> Self {
+            unsafe { Sse2(_mm_or_si128(self.0, other.0)) }
+        }
+
+        fn xor(self, other: Self) -> Self {
+            unsafe { Sse2(_mm_xor_si128(self.0, other.0)) }
+        }
+
+        fn rotate_left(self, n: u32) -> Self {
+            unsafe {
+                let left = _mm_sll_epi32(self.0, _mm_cvtsi32_si128(n as i32));
+                let right = _mm_srl_epi32(self.0, _mm_cvtsi32_si128(32 - n as i32));
+                Sse2(_mm_or_si128(left, right))
+            }
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct Sse2x2([Sse2; 2]);
+
+    impl Lanes for Sse2x2 {
+        const WIDTH: usize = 8;
+
+        fn splat(v: u32) -> Self {
+            Sse2x2([Sse2::splat(v); 2])
+        }
+
+        fn from_words(words: &[u32]) -> Self {
+            Sse2x2([Sse2::from_words(&words[..4]), Sse2::from_words(&words[4..])])
+        }
+
+        fn to_words(self, words: &mut [u32]) {
+            self.0[0].to_words(&mut words[..4]);
+            self.0[1].to_words(&mut words[4..]);
+        }
+
+        fn add(self, other: Self) -> Self {
+            Sse2x2([self.0[0].add(other.0[0]), self.0[1].add(other.0[1])])
+        }
+
+        fn and(self, other: Self) -> Self {
+            Sse2x2([self.0[0].and(other.0[0]), self.0[1].and(other.0[1])])
+        }
+
+        fn or(self, other: Self) -> Self {
+            Sse2x2([self.0[0].or(other.0[0]), self.0[1].or(other.0[1])])
+        }
+
+        fn xor(self, other: Self) -> Self {
+            Sse2x2([self.0[0].xor(other.0[0]), self.0[1].xor(other.0[1])])
+        }
+
+        fn rotate_left(self, n: u32) -> Self {
+            Sse2x2([self.0[0].rotate_left(n), self.0[1].rotate_left(n)])
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+type Lanes4 = sse2::Sse2;
+#[cfg(target_arch = "x86_64")]
+type Lanes8 = sse2::Sse2x2;
+#[cfg(not(target_arch = "x86_64"))]
+type Lanes4 = [u32; 4];
+#[cfg(not(target_arch = "x86_64"))]
+type Lanes8 = [u32; 8];
+
+fn compress<L: Lanes>(state: &mut [L; 4], block: &[L; 16]) {
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => (b.and(c).or(b.not().and(d)), i),
+            1 => (d.and(b).or(d.not().and(c)), (5 * i + 1) % 16),
+            2 => (b.xor(c).xor(d), (3 * i + 5) % 16),
+            _ => (c.xor(b.or(d.not())), (7 * i) % 16),
+        };
+        let f = f.add(a).add(L::splat(SINES[i])).add(block[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.add(f.rotate_left(SHIFTS[i]));
+    }
+
+    state[0] = state[0].add(a);
+    state[1] = state[1].add(b);
+    state[2] = state[2].add(c);
+    state[3] = state[3].add(d);
+}
+
+fn block_count(len: usize) -> usize {
+    (len + 8) / 64 + 1
+}
+
+// Reads word `word` of block `block` of the padded message, without building it.
+fn padded_word(message: &[u8], block: usize, word: usize) -> u32 {
+    let last_block = block + 1 == block_count(message.len());
+    if last_block && word >= 14 {
+        let bits = (message.len() as u64).wrapping_mul(8);
+        return (bits >> (32 * (word - 14))) as u32;
+    }
+
+    let start = block * 64 + word * 4;
+    let mut bytes = [0u8; 4];
+    for (k, byte) in bytes.iter_mut().enumerate() {
+        let pos = start + k;
+        if pos < message.len() {
+            *byte = message[pos];
+        } else if pos == message.len() {
+            *byte = 0x80;
+        }
+    }
+    u32::from_le_bytes(bytes)
+}
+
+fn digest_lanes<L: Lanes, const N: usize>(messages: &[&[u8]; N]) -> [[u8; 16]; N] {
+    assert_eq!(L::WIDTH, N);
+    let blocks = block_count(messages[0].len());
+    if messages.iter().any(|m| block_count(m.len()) != blocks) {
+        return std::array::from_fn(|lane| digest(messages[lane]));
+    }
+
+    let mut state = INITIAL_STATE.map(L::splat);
+    let mut words = [0u32; N];
+    for block in 0..blocks {
+        let schedule: [L; 16] = std::array::from_fn(|word| {
+            for (lane, message) in messages.iter().enumerate() {
+                words[lane] = padded_word(message, block, word);
+            }
+            L::from_words(&words)
+        });
+        compress(&mut state, &schedule);
+    }
+
+    let mut digests = [[0u8; 16]; N];
+    for (i, register) in state.iter().enumerate() {
+        register.to_words(&mut words);
+        for (lane, digest) in digests.iter_mut().enumerate() {
+            digest[i * 4..i * 4 + 4].copy_from_slice(&words[lane].to_le_bytes());
+        }
+    }
+    digests
+}
+
+pub fn digest(message: &[u8]) -> [u8; 16] {
+    digest_lanes::<[u32; 1], 1>(&[message])[0]
+}
+
+pub fn digest_x4(messages: &[&[u8]; 4]) -> [[u8; 16]; 4] {
+    digest_lanes::<Lanes4, 4>(messages)
+}
+
+pub fn digest_x8(messages: &[&[u8]; 8]) -> [[u8; 16]; 8] {
+    digest_lanes::<Lanes8, 8>(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_1321_VECTORS: [(&str, &str); 7] = [
+        ("", "d41d8cd98f00b204e9800998ecf8427e"),
+        ("a", "0cc175b9c0f1b6a831c399e269772661"),
+        ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+        ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+        ("abcdefghijklmnopqrstuvwxyz", "c3fcd3d76192e4007dfb496cca67e13b"),
+        ("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789", "d174ab98d277d9f5a5611c2c9f419d9f"),
+        ("12345678901234567890123456789012345678901234567890123456789012345678901234567890", "57edf4a22be3c955ac49da2e2107b67a"),
+    ];
+
+    fn hex(digest: &[u8; 16]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_rfc_1321_vectors() {
+        for (message, expected) in RFC_1321_VECTORS {
+            assert_eq!(hex(&digest(message.as_bytes())), expected);
+        }
+    }
+
+    #[test]
+    fn test_lanes_match_md5_crate() {
+        let messages: Vec<String> = (0..8).map(|i| format!("abcdef{}", 609040 + i)).collect();
+        let refs: Vec<&[u8]> = messages.iter().map(|m| m.as_bytes()).collect();
+
+        let x8 = digest_x8(&refs.clone().try_into().unwrap());
+        let x4 = digest_x4(&refs[..4].try_into().unwrap());
+        let portable = digest_lanes::<[u32; 8], 8>(&refs.clone().try_into().unwrap());
+        for (lane, message) in messages.iter().enumerate() {
+            let expected = md5::compute(message).0;
+            assert_eq!(x8[lane], expected);
+            assert_eq!(portable[lane], expected);
+            if lane < 4 {
+                assert_eq!(x4[lane], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lanes_with_mixed_lengths() {
+        let messages: Vec<String> = (0..64).map(|n| "x".repeat(n)).collect();
+        for chunk in messages.windows(4) {
+            let refs: Vec<&[u8]> = chunk.iter().map(|m| m.as_bytes()).collect();
+            let digests = digest_x4(&refs.try_into().unwrap());
+            for (lane, message) in chunk.iter().enumerate() {
+                assert_eq!(digests[lane], md5::compute(message).0);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+// Hash functions usable for the prefix search. Each absorbs the secret once and
+// finishes the nonce digits from a clone of that state; only the first 128 bits
+// of the digest are compared against the difficulty.
+
+use sha1::Digest as _;
+
+use crate::{DecimalCounter, Difficulty};
+
+pub trait ProofOfWorkHash {
+    type State: Clone;
+    const NAME: &'static str;
+
+    fn absorb(prefix: &[u8]) -> Self::State;
+    fn finish(state: Self::State, tail: &[u8]) -> [u8; 16];
+}
+
+pub struct Md5;
+pub struct Sha1;
+pub struct Sha256;
+
+fn leading_bytes(digest: &[u8]) -> [u8; 16] {
+    digest[..16].try_into().unwrap()
+}
+
+impl ProofOfWorkHash for Md5 {
+    type State = md5::Context;
+    const NAME: &'static str = "md5";
+
+    fn absorb(prefix: &[u8]) -> Self::State {
+        let mut context = md5::Context::new();
+        context.consume(prefix);
+        context
+    }
+
+    fn finish(mut state: Self::State, tail: &[u8]) -> [u8; 16] {
+        state.consume(tail);
+        state.compute().0
+    }
+}
+
+impl ProofOfWorkHash for Sha1 {
+    type State = sha1::Sha1;
+    const NAME: &'static str = "sha1";
+
+    fn absorb(prefix: &[u8]) -> Self::State {
+        sha1::Sha1::new_with_prefix(prefix)
+    }
+
+    fn finish(mut state: Self::State, tail: &[u8]) -> [u8; 16] {
+        state.update(tail);
+        leading_bytes(&state.finalize())
+    }
+}
+
+impl ProofOfWorkHash for Sha256 {
+    type State = sha2::Sha256;
+    const NAME: &'static str = "sha256";
+
+    fn absorb(prefix: &[u8]) -> Self::State {
+        sha2::Sha256::new_with_prefix(prefix)
+    }
+
+    fn finish(mut state: Self::State, tail: &[u8]) -> [u8; 16] {
+        state.update(tail);
+        leading_bytes(&state.finalize())
+    }
+}
+
+pub struct ValidNonces<'a, H: ProofOfWorkHash> {
+    prefix: H::State,
+    difficulty: &'a Difficulty,
+    nonce: DecimalCounter,
+    next: Option<u32>,
+}
+
+impl<H: ProofOfWorkHash> Iterator for ValidNonces<'_, H> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        while let Some(i) = self.next {
+            let valid = self.difficulty.is_met(&H::finish(self.prefix.clone(), self.nonce.as_bytes()));
+            self.next = i.checked_add(1);
+            if self.next.is_some() {
+                self.nonce.increment();
+            }
+            if valid {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
+pub fn valid_nonces<'a, H: ProofOfWorkHash>(secret: &str, difficulty: &'a Difficulty) -> ValidNonces<'a, H>
+{
+    ValidNonces { prefix: H::absorb(secret.as_bytes()), difficulty, nonce: DecimalCounter::new(), next: Some(0) }
+}
+
+pub fn brute_force_with<H: ProofOfWorkHash>(secret: &str, difficulty: &Difficulty) -> u32
+{
+    valid_nonces::<H>(secret, difficulty).next().expect("Nonce space exhausted")
+}
+
+// Hits are geometrically distributed, so the gap between consecutive valid
+// nonces should average the expected number of attempts (16^d for d nibbles).
+// The first gap is counted from nonce 0.
+pub struct Spacing {
+    pub hits: usize,
+    pub mean: f64,
+    pub min: u32,
+    pub max: u32,
+    pub expected: f64,
+}
+
+pub fn spacing(nonces: &[u32], difficulty: &Difficulty) -> Spacing
+{
+    let gaps: Vec<u32> = nonces.iter()
+        .scan(None, |prev: &mut Option<u32>, &n| {
+            let gap = prev.map_or(n + 1, |p| n - p);
+            *prev = Some(n);
+            Some(gap)
+        })
+        .collect();
+    let mean = if gaps.is_empty() { 0.0 } else { gaps.iter().map(|&g| g as f64).sum::<f64>() / gaps.len() as f64 };
+
+    Spacing {
+        hits: nonces.len(),
+        mean,
+        min: gaps.iter().copied().min().unwrap_or(0),
+        max: gaps.iter().copied().max().unwrap_or(0),
+        expected: difficulty.expected_attempts(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_finish_matches_full_digest() {
+        assert_eq!(hex(&Md5::finish(Md5::absorb(b"ab"), b"c")), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hex(&Sha1::finish(Sha1::absorb(b"ab"), b"c")), "a9993e364706816aba3e25717850c26c");
+        assert_eq!(hex(&Sha256::finish(Sha256::absorb(b"ab"), b"c")), "ba7816bf8f01cfea414140de5dae2223");
+    }
+
+    #[test]
+    fn test_brute_force_with_md5() {
+        assert_eq!(brute_force_with::<Md5>("abcdef", &crate::FIVE_ZEROS), 609043);
+    }
+
+    #[test]
+    fn test_brute_force_with_sha1() {
+        assert_eq!(brute_force_with::<Sha1>("abcdef", &Difficulty::zero_nibbles(4)), 185343);
+    }
+
+    #[test]
+    fn test_brute_force_with_sha256() {
+        assert_eq!(brute_force_with::<Sha256>("abcdef", &Difficulty::zero_nibbles(4)), 71479);
+    }
+
+    #[test]
+    fn test_valid_nonces_in_order() {
+        let difficulty = Difficulty::zero_nibbles(3);
+        let nonces: Vec<u32> = valid_nonces::<Md5>("abcdef", &difficulty).take(5).collect();
+        assert_eq!(nonces.len(), 5);
+        assert!(nonces.windows(2).all(|w| w[0] < w[1]));
+        for n in 0..=nonces[4] {
+            let expected = nonces.contains(&n);
+            assert_eq!(format!("{:x}", md5::compute(format!("abcdef{}", n))).starts_with("000"), expected);
+        }
+    }
+
+    #[test]
+    fn test_spacing() {
+        let stats = spacing(&[9, 19, 49], &Difficulty::zero_nibbles(1));
+        assert_eq!(stats.hits, 3);
+        assert_eq!((stats.min, stats.max), (10, 30));
+        assert!((stats.mean - 50.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.expected, 16.0);
+    }
+}
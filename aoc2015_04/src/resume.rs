@@ -0,0 +1,176 @@
+// Searches a slice of the nonce space, reporting progress at regular intervals
+// so that the caller can display it and write checkpoints.
+
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::pow::ProofOfWorkHash;
+use crate::{DecimalCounter, Difficulty};
+
+pub struct Progress {
+    pub next: u32,
+    pub searched: u64,
+    pub elapsed: Duration,
+    pub expected_attempts: f64,
+}
+
+impl Progress
+{
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 { self.searched as f64 / secs } else { 0.0 }
+    }
+
+    // Time left until the expected number of attempts for the difficulty is
+    // reached; None while no rate is known yet.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        if rate <= 0.0 {
+            return None;
+        }
+        let remaining = (self.expected_attempts - self.searched as f64).max(0.0);
+        Some(Duration::from_secs_f64(remaining / rate))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Checkpoint {
+    pub secret: String,
+    pub hash: String,
+    pub mask: u128,
+    pub target: u128,
+    pub next: u32,
+}
+
+impl Checkpoint
+{
+    pub fn new(secret: &str, hash: &str, difficulty: &Difficulty, next: u32) -> Checkpoint {
+        Checkpoint { secret: secret.to_string(), hash: hash.to_string(), mask: difficulty.mask, target: difficulty.target, next }
+    }
+
+    pub fn matches(&self, secret: &str, hash: &str, difficulty: &Difficulty) -> bool {
+        self.secret == secret && self.hash == hash && self.mask == difficulty.mask && self.target == difficulty.target
+    }
+
+    // Written to a temporary file first so an interrupted save never leaves a
+    // truncated checkpoint behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, format!("{}\n{:x}\n{:x}\n{}\n{}\n", self.secret, self.mask, self.target, self.next, self.hash))?;
+        fs::rename(tmp, path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Checkpoint> {
+        let data = fs::read_to_string(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Malformed checkpoint file");
+        let mut lines = data.lines();
+        let secret = lines.next().ok_or_else(invalid)?.to_string();
+        let mask = u128::from_str_radix(lines.next().ok_or_else(invalid)?, 16).map_err(|_| invalid())?;
+        let target = u128::from_str_radix(lines.next().ok_or_else(invalid)?, 16).map_err(|_| invalid())?;
+        let next = lines.next().ok_or_else(invalid)?.parse::<u32>().map_err(|_| invalid())?;
+        let hash = lines.next().ok_or_else(invalid)?.to_string();
+        Ok(Checkpoint { secret, hash, mask, target, next })
+    }
+}
+
+pub fn search_range<H: ProofOfWorkHash, F>(secret: &str, difficulty: &Difficulty, range: Range<u32>, report_every: u32, mut report: F) -> Option<u32>
+where
+    F: FnMut(&Progress),
+{
+    // A checkpoint can already be past the end of the range.
+    if range.is_empty() {
+        return None;
+    }
+    let prefix = H::absorb(secret.as_bytes());
+
+    let started = Instant::now();
+    let mut nonce = DecimalCounter::starting_at(range.start);
+    let progress = |next: u32| Progress {
+        next,
+        searched: (next - range.start) as u64,
+        elapsed: started.elapsed(),
+        expected_attempts: difficulty.expected_attempts(),
+    };
+
+    for i in range.clone() {
+        if i != range.start && (i - range.start).is_multiple_of(report_every.max(1)) {
+            report(&progress(i));
+        }
+        if difficulty.is_met(&H::finish(prefix.clone(), nonce.as_bytes())) {
+            return Some(i);
+        }
+        nonce.increment();
+    }
+
+    report(&progress(range.end));
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pow::{Md5, Sha256};
+    use crate::{FIVE_ZEROS, SIX_ZEROS};
+
+    #[test]
+    fn test_search_range_finds_nonce() {
+        assert_eq!(search_range::<Md5, _>("abcdef", &FIVE_ZEROS, 600000..700000, 10000, |_| ()), Some(609043));
+    }
+
+    #[test]
+    fn test_search_range_misses_nonce() {
+        let mut reports = Vec::new();
+        let found = search_range::<Md5, _>("abcdef", &FIVE_ZEROS, 0..1000, 300, |p| reports.push(p.next));
+        assert_eq!(found, None);
+        assert_eq!(reports, vec![300, 600, 900, 1000]);
+    }
+
+    #[test]
+    fn test_search_range_with_sha256() {
+        assert_eq!(search_range::<Sha256, _>("abcdef", &Difficulty::zero_nibbles(4), 70000..80000, 1000, |_| ()), Some(71479));
+    }
+
+    #[test]
+    fn test_search_range_empty() {
+        // Resuming from a checkpoint at 10 with --end 5.
+        let (next, end) = (10, 5);
+        let mut reports = 0;
+        assert_eq!(search_range::<Md5, _>("abcdef", &FIVE_ZEROS, next..end, 300, |_| reports += 1), None);
+        assert_eq!(reports, 0);
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let path = std::env::temp_dir().join("aoc2015_04_checkpoint_test.txt");
+        let checkpoint = Checkpoint::new("abcdef", "md5", &FIVE_ZEROS, 123456);
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.matches("abcdef", "md5", &FIVE_ZEROS));
+        assert!(!loaded.matches("pqrstuv", "md5", &FIVE_ZEROS));
+        assert!(!loaded.matches("abcdef", "sha1", &FIVE_ZEROS));
+        assert!(!loaded.matches("abcdef", "md5", &SIX_ZEROS));
+    }
+
+    #[test]
+    fn test_checkpoint_without_hash() {
+        let path = std::env::temp_dir().join("aoc2015_04_checkpoint_without_hash_test.txt");
+        fs::write(&path, "abcdef\nfffff\n0\n123456\n").unwrap();
+        let loaded = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_progress_eta() {
+        let progress = Progress { next: 1000, searched: 1000, elapsed: Duration::from_secs(1), expected_attempts: 3000.0 };
+        assert_eq!(progress.rate(), 1000.0);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc2015_05::automaton::{Automaton, RULESET1, RULESET2};
+use aoc2015_05::count::LOWERCASE;
+use aoc2015_05::generate::{random_words, Rng};
+use aoc2015_05::*;
+
+fn rulesets(c: &mut Criterion) {
+    let words = random_words(&mut Rng::new(2015), 100_000, 16, LOWERCASE);
+    let mut group = c.benchmark_group("100k_words");
+    group.sample_size(10);
+    group.bench_function("reference_ruleset1", |b| b.iter(|| {
+        words.iter().filter(|w| apply_ruleset(w, &[rule1, rule2, rule3])).count()
+    }));
+    group.bench_function("automaton_ruleset1", |b| b.iter(|| {
+        let mut automaton = Automaton::new(RULESET1);
+        words.iter().filter(|w| automaton.evaluate(w)).count()
+    }));
+    group.bench_function("reference_ruleset2", |b| b.iter(|| {
+        words.iter().filter(|w| apply_ruleset(w, &[rule4, rule5])).count()
+    }));
+    group.bench_function("automaton_ruleset2", |b| b.iter(|| {
+        let mut automaton = Automaton::new(RULESET2);
+        words.iter().filter(|w| automaton.evaluate(w)).count()
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, rulesets);
+criterion_main!(benches);
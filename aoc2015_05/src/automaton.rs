@@ -0,0 +1,288 @@
+// Evaluates any combination of rule1..rule5 in a single pass over a string,
+// updating the state of every active rule for each symbol. rule1..rule5
+// remain the reference implementation.
+//
+// Symbols are chars by default, matching the reference rules. ASCII input
+// takes a byte path that skips UTF-8 decoding; with Alphabet::Graphemes each
+// extended grapheme cluster counts as one symbol, so "e\u{301}" is a single
+// letter that is not a vowel.
+
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const RULE1: u8 = 1 << 0;
+pub const RULE2: u8 = 1 << 1;
+pub const RULE3: u8 = 1 << 2;
+pub const RULE4: u8 = 1 << 3;
+pub const RULE5: u8 = 1 << 4;
+pub const RULESET1: u8 = RULE1 | RULE2 | RULE3;
+pub const RULESET2: u8 = RULE4 | RULE5;
+
+const ASCII_PAIRS: usize = 128 * 128;
+const FIRST_CLUSTER_SYMBOL: u32 = char::MAX as u32 + 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alphabet {
+    Chars,
+    Graphemes,
+}
+
+// Chars are their own code point; multi-char grapheme clusters are numbered
+// past char::MAX as they are met in a string.
+type Symbol = u32;
+
+fn ascii(c: Symbol) -> Option<u8> {
+    u8::try_from(c).ok().filter(u8::is_ascii)
+}
+
+// First offset of every pair seen in the current string. ASCII pairs live in a
+// flat table stamped with the generation of the string that wrote them, so a
+// reset never has to clear it; other pairs go through a map.
+struct PairTable {
+    generation: u32,
+    ascii: Vec<(u32, usize)>,
+    other: HashMap<(Symbol, Symbol), usize>,
+}
+
+impl PairTable
+{
+    fn new() -> PairTable {
+        PairTable { generation: 1, ascii: vec![(0, 0); ASCII_PAIRS], other: HashMap::new() }
+    }
+
+    fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.ascii.fill((0, 0));
+            self.generation = 1;
+        }
+        self.other.clear();
+    }
+
+    fn first_offset(&mut self, pair: (Symbol, Symbol), offset: usize) -> usize {
+        if pair.0 < 128 && pair.1 < 128 {
+            let entry = &mut self.ascii[pair.0 as usize * 128 + pair.1 as usize];
+            if entry.0 != self.generation {
+                *entry = (self.generation, offset);
+            }
+            entry.1
+        } else {
+            *self.other.entry(pair).or_insert(offset)
+        }
+    }
+}
+
+pub struct Automaton {
+    rules: u8,
+    index: usize,
+    vowels: usize,
+    prev: Option<Symbol>,
+    prevprev: Option<Symbol>,
+    pairs: PairTable,
+    found: u8,
+    forbidden: bool,
+}
+
+impl Automaton
+{
+    pub fn new(rules: u8) -> Automaton {
+        Automaton {
+            rules,
+            index: 0,
+            vowels: 0,
+            prev: None,
+            prevprev: None,
+            pairs: PairTable::new(),
+            found: 0,
+            forbidden: false,
+        }
+    }
+
+    // Keeps the pair table so one automaton can scan many strings.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.vowels = 0;
+        self.prev = None;
+        self.prevprev = None;
+        self.pairs.reset();
+        self.found = 0;
+        self.forbidden = false;
+    }
+
+    fn active(&self, rule: u8) -> bool {
+        self.rules & rule != 0
+    }
+
+    pub fn step(&mut self, c: char) {
+        self.step_symbol(c as Symbol);
+    }
+
+    fn step_symbol(&mut self, c: Symbol) {
+        if self.active(RULE1) && matches!(ascii(c), Some(b'a' | b'e' | b'i' | b'o' | b'u')) {
+            self.vowels += 1;
+        }
+        if let Some(prev) = self.prev {
+            if self.active(RULE2) && prev == c {
+                self.found |= RULE2;
+            }
+            let forbidden = matches!((ascii(prev), ascii(c)),
+                (Some(b'a'), Some(b'b')) | (Some(b'c'), Some(b'd')) | (Some(b'p'), Some(b'q')) | (Some(b'x'), Some(b'y')));
+            if self.active(RULE3) && forbidden {
+                self.forbidden = true;
+            }
+            if self.active(RULE4) && self.found & RULE4 == 0 {
+                let offset = self.index - 1;
+                let first = self.pairs.first_offset((prev, c), offset);
+                if first + 2 <= offset {
+                    self.found |= RULE4;
+                }
+            }
+        }
+        if self.active(RULE5) && self.prevprev == Some(c) {
+            self.found |= RULE5;
+        }
+        self.prevprev = self.prev;
+        self.prev = Some(c);
+        self.index += 1;
+    }
+
+    // True once no further input can make the string nice.
+    pub fn rejected(&self) -> bool {
+        self.active(RULE3) && self.forbidden
+    }
+
+    pub fn accepts(&self) -> bool {
+        !self.rejected()
+            && (!self.active(RULE1) || self.vowels >= 3)
+            && self.found & (RULE2 | RULE4 | RULE5) == self.rules & (RULE2 | RULE4 | RULE5)
+    }
+
+    fn run(&mut self, symbols: impl Iterator<Item = Symbol>) -> bool {
+        self.reset();
+        for c in symbols {
+            self.step_symbol(c);
+            if self.rejected() {
+                return false;
+            }
+        }
+        self.accepts()
+    }
+
+    // Fast path: every byte is one symbol, which is only char-correct for ASCII.
+    pub fn evaluate_ascii(&mut self, s: &[u8]) -> bool {
+        self.run(s.iter().map(|&b| b as Symbol))
+    }
+
+    pub fn evaluate(&mut self, s: &str) -> bool {
+        self.evaluate_with(s, Alphabet::Chars)
+    }
+
+    pub fn evaluate_with(&mut self, s: &str, alphabet: Alphabet) -> bool {
+        // A CR LF pair is a single grapheme cluster, so only take the byte path
+        // for graphemes when no CR is present.
+        let bytes_are_symbols = s.is_ascii() && (alphabet == Alphabet::Chars || !s.contains('\r'));
+        if bytes_are_symbols {
+            return self.evaluate_ascii(s.as_bytes());
+        }
+        match alphabet {
+            Alphabet::Chars => self.run(s.chars().map(|c| c as Symbol)),
+            Alphabet::Graphemes => {
+                let mut clusters: HashMap<&str, Symbol> = HashMap::new();
+                let symbols = s.graphemes(true).map(|g| {
+                    let mut chars = g.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => c as Symbol,
+                        _ => {
+                            let next = FIRST_CLUSTER_SYMBOL + clusters.len() as Symbol;
+                            *clusters.entry(g).or_insert(next)
+                        }
+                    }
+                });
+                self.run(symbols)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::generate::{random_words, Rng};
+    use crate::{apply_ruleset, rule1, rule2, rule3, rule4, rule5, RuleFn};
+
+    #[test]
+    fn test_matches_reference_rules()
+    {
+        let reference: [(u8, RuleFn); 5] = [(RULE1, rule1), (RULE2, rule2), (RULE3, rule3), (RULE4, rule4), (RULE5, rule5)];
+        let mut automaton = Automaton::new(0);
+        let mut rng = Rng::new(36);
+        for alphabet in ["abcdefghijklmnopqrstuvwxyz", "abcxy", "aqp"] {
+            for s in random_words(&mut rng, 2000, 12, alphabet) {
+                for (rule, reference) in reference {
+                    automaton.rules = rule;
+                    assert_eq!(automaton.evaluate(&s), reference(&s), "rule {:b} on {}", rule, s);
+                }
+                automaton.rules = RULESET1;
+                assert_eq!(automaton.evaluate(&s), apply_ruleset(&s, &[rule1, rule2, rule3]), "{}", s);
+                automaton.rules = RULESET2;
+                assert_eq!(automaton.evaluate(&s), apply_ruleset(&s, &[rule4, rule5]), "{}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_examples()
+    {
+        let mut ruleset1 = Automaton::new(RULESET1);
+        assert!(ruleset1.evaluate("ugknbfddgicrmopn"));
+        assert!(!ruleset1.evaluate("haegwjzuvuyypxyu"));
+        let mut ruleset2 = Automaton::new(RULESET2);
+        assert!(ruleset2.evaluate("qjhvhtzxzqqjkmpb"));
+        assert!(!ruleset2.evaluate("ieodomkazucvgmuy"));
+        assert!(ruleset2.evaluate("aaaaba"));
+        assert!(ruleset2.evaluate("éaéa"));
+        assert!(!ruleset2.evaluate("ééé"));
+    }
+
+    #[test]
+    fn test_short_strings()
+    {
+        let mut automaton = Automaton::new(RULESET1 | RULESET2);
+        for s in ["", "a", "é", "€", "ba"] {
+            for alphabet in [Alphabet::Chars, Alphabet::Graphemes] {
+                assert!(!automaton.evaluate_with(s, alphabet), "{}", s);
+            }
+        }
+        let mut rule3_only = Automaton::new(RULE3);
+        assert!(rule3_only.evaluate(""));
+        assert!(rule3_only.evaluate_ascii(b""));
+    }
+
+    #[test]
+    fn test_non_ascii_matches_reference()
+    {
+        let reference: [(u8, RuleFn); 5] = [(RULE1, rule1), (RULE2, rule2), (RULE3, rule3), (RULE4, rule4), (RULE5, rule5)];
+        let mut automaton = Automaton::new(0);
+        for s in random_words(&mut Rng::new(37), 2000, 10, "aébcxy€ü") {
+            for (rule, reference) in reference {
+                automaton.rules = rule;
+                assert_eq!(automaton.evaluate(&s), reference(&s), "rule {:b} on {}", rule, s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_graphemes()
+    {
+        // 'e' followed by a combining acute accent is one letter, not a vowel.
+        let decomposed = "ae\u{301}ie\u{301}";
+        assert!(Automaton::new(RULE1).evaluate(decomposed));
+        assert!(!Automaton::new(RULE1).evaluate_with(decomposed, Alphabet::Graphemes));
+        assert!(Automaton::new(RULE5).evaluate_with("e\u{301}xe\u{301}", Alphabet::Graphemes));
+        assert!(!Automaton::new(RULE5).evaluate_with("e\u{301}xe", Alphabet::Graphemes));
+        assert!(Automaton::new(RULE2).evaluate_with("\r\n\r\n", Alphabet::Graphemes));
+        assert!(!Automaton::new(RULE2).evaluate_with("\r\n", Alphabet::Graphemes));
+    }
+}
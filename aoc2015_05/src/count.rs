@@ -0,0 +1,230 @@
+// Exact counts of the nice strings of a given length over an alphabet, with
+// the same semantics as rule1..rule5.
+//
+// Ruleset 1 is a transfer count: whether a string can still become nice only
+// depends on its last letter, its vowel count (capped at 3) and whether it has
+// had a double letter, and a forbidden pair drops it for good.
+//
+// Ruleset 2 has no such small state, since a repeated pair depends on every
+// pair seen so far; counting the strings that never repeat one means counting
+// edge-distinct walks in a complete digraph. Two ranges are still exact:
+// - with k letters, any string longer than k*k + k + 1 repeats a pair (there
+//   are k*k pairs and only a run like "aaa" may reuse one in place), so the
+//   count is that of rule5, which needs only the last two letters;
+// - short strings are counted by their pattern of equal letters, because rule4
+//   and rule5 only compare letters with each other: a pattern with m distinct
+//   letters stands for k(k-1)..(k-m+1) strings.
+// Lengths in between are an error: the strings that never repeat a pair are
+// trails in the complete digraph on k letters, which depend on the full set of
+// pairs used so far, and no exact method here gets past a dozen letters.
+
+use num_bigint::BigUint;
+
+use crate::automaton::{RULESET1, RULESET2};
+use crate::{rule4, rule5};
+
+pub const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+pub const PATTERN_MAX_LEN: usize = 12;
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+fn is_forbidden(prev: char, c: char) -> bool {
+    matches!((prev, c), ('a', 'b') | ('c', 'd') | ('p', 'q') | ('x', 'y'))
+}
+
+// Fails for rule combinations other than the two rulesets, and for ruleset 2
+// lengths that are neither short enough to enumerate nor long enough to force
+// a repeated pair.
+pub fn count_nice(rules: u8, alphabet: &[char], n: usize) -> Result<BigUint, String>
+{
+    match rules {
+        RULESET1 => Ok(count_ruleset1(alphabet, n)),
+        RULESET2 => count_ruleset2(alphabet, n).ok_or_else(|| {
+            format!("Ruleset 2 counts over {} letters are only exact for lengths up to {} and from {} on",
+                    alphabet.len(), PATTERN_MAX_LEN, forced_repeat_len(alphabet.len()))
+        }),
+        _ => Err("Only ruleset 1 and ruleset 2 can be counted".to_string()),
+    }
+}
+
+// Shortest length at which every string over k letters repeats a pair.
+fn forced_repeat_len(k: usize) -> usize
+{
+    k * k + k + 2
+}
+
+pub fn count_ruleset1(alphabet: &[char], n: usize) -> BigUint
+{
+    // State: last letter (alphabet.len() before the first one), vowels seen
+    // capped at 3, and whether a double letter was seen.
+    let k = alphabet.len();
+    let index = |last: usize, vowels: usize, double: usize| (last * 4 + vowels) * 2 + double;
+    let mut counts = vec![BigUint::ZERO; (k + 1) * 8];
+    counts[index(k, 0, 0)] = BigUint::from(1u8);
+
+    for _ in 0..n {
+        let mut next = vec![BigUint::ZERO; (k + 1) * 8];
+        for last in 0..=k {
+            for vowels in 0..4 {
+                for double in 0..2 {
+                    let count = &counts[index(last, vowels, double)];
+                    if *count == BigUint::ZERO {
+                        continue;
+                    }
+                    for (i, &c) in alphabet.iter().enumerate() {
+                        if last < k && is_forbidden(alphabet[last], c) {
+                            continue;
+                        }
+                        let vowels = (vowels + is_vowel(c) as usize).min(3);
+                        let double = double | (last == i) as usize;
+                        next[index(i, vowels, double)] += count;
+                    }
+                }
+            }
+        }
+        counts = next;
+    }
+
+    (0..k).map(|last| &counts[index(last, 3, 1)]).sum()
+}
+
+pub fn count_ruleset2(alphabet: &[char], n: usize) -> Option<BigUint>
+{
+    let k = alphabet.len();
+    if n >= forced_repeat_len(k) {
+        let total = BigUint::from(k).pow(n as u32);
+        return Some(total - count_without_gap_repeat(k, n));
+    }
+    if n <= PATTERN_MAX_LEN {
+        return Some(count_patterns(alphabet, n));
+    }
+    None
+}
+
+// Strings with no letter repeated one letter later; only equality matters, so
+// the state is whether the last two letters are equal.
+fn count_without_gap_repeat(k: usize, n: usize) -> BigUint
+{
+    if n <= 2 {
+        return BigUint::from(k).pow(n as u32);
+    }
+    // Strings of the current length ending in two equal or two different letters.
+    let mut equal = BigUint::from(k);
+    let mut different = BigUint::from(k * k.saturating_sub(1));
+    for _ in 2..n {
+        // After "xx" any letter but x; after "xy" any letter but x, and y makes "yy".
+        let next_different = &equal * k.saturating_sub(1) + &different * k.saturating_sub(2);
+        equal = std::mem::replace(&mut different, next_different);
+    }
+    equal + different
+}
+
+fn count_patterns(alphabet: &[char], n: usize) -> BigUint
+{
+    // Strings with m distinct letters from the alphabet, in order of first use.
+    let arrangements: Vec<BigUint> = (0..=n)
+        .scan(BigUint::from(1u8), |product, m| {
+            let current = product.clone();
+            *product *= alphabet.len().saturating_sub(m);
+            Some(current)
+        })
+        .collect();
+    let mut prefix = String::with_capacity(n);
+    let mut total = BigUint::ZERO;
+    extend_pattern(alphabet, n, &arrangements, &mut prefix, 0, &mut total);
+    total
+}
+
+fn extend_pattern(alphabet: &[char], n: usize, arrangements: &[BigUint], prefix: &mut String, used: usize, total: &mut BigUint)
+{
+    let len = prefix.chars().count();
+    // Both rules only ever become true, so every completion of this prefix is nice.
+    if rule4(prefix) && rule5(prefix) {
+        *total += &arrangements[used] * BigUint::from(alphabet.len()).pow((n - len) as u32);
+        return;
+    }
+    if len == n {
+        return;
+    }
+    for (i, &c) in alphabet.iter().enumerate().take(used + 1) {
+        prefix.push(c);
+        extend_pattern(alphabet, n, arrangements, prefix, used.max(i + 1), total);
+        prefix.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{apply_ruleset, rule1, rule2, rule3, RuleFn};
+
+    // Tries every string of length n over the alphabet with the reference rules.
+    fn brute_force(alphabet: &[char], n: usize, rules: &[RuleFn]) -> BigUint {
+        let k = alphabet.len();
+        let count = (0..k.pow(n as u32))
+            .filter(|&number| {
+                let mut rest = number;
+                let s: String = (0..n).map(|_| { let c = alphabet[rest % k]; rest /= k; c }).collect();
+                apply_ruleset(&s, rules)
+            })
+            .count();
+        BigUint::from(count)
+    }
+
+    #[test]
+    fn test_ruleset1_matches_brute_force()
+    {
+        let alphabet: Vec<char> = "abcdeixy".chars().collect();
+        for n in 0..=5 {
+            assert_eq!(count_ruleset1(&alphabet, n), brute_force(&alphabet, n, &[rule1, rule2, rule3]), "n = {}", n);
+        }
+        let lowercase: Vec<char> = LOWERCASE.chars().collect();
+        for n in 0..=4 {
+            assert_eq!(count_ruleset1(&lowercase, n), brute_force(&lowercase, n, &[rule1, rule2, rule3]), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_ruleset2_matches_brute_force()
+    {
+        let alphabet: Vec<char> = "abcdeixy".chars().collect();
+        for n in 0..=6 {
+            assert_eq!(count_ruleset2(&alphabet, n), Some(brute_force(&alphabet, n, &[rule4, rule5])), "n = {}", n);
+        }
+        let lowercase: Vec<char> = LOWERCASE.chars().collect();
+        for n in 0..=4 {
+            assert_eq!(count_ruleset2(&lowercase, n), Some(brute_force(&lowercase, n, &[rule4, rule5])), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_long_strings_always_repeat_a_pair()
+    {
+        // Two letters: strings past 2*2 + 2 + 1 = 7 letters always repeat a pair.
+        let alphabet = ['a', 'b'];
+        assert!(!rule4("aaabbba"));
+        for n in 8..=12 {
+            let expected = brute_force(&alphabet, n, &[rule4, rule5]);
+            assert_eq!(count_without_gap_repeat(2, n), BigUint::from(2u8).pow(n as u32) - &expected);
+            assert_eq!(count_patterns(&alphabet, n), expected);
+            assert_eq!(count_ruleset2(&alphabet, n), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_large_lengths()
+    {
+        let lowercase: Vec<char> = LOWERCASE.chars().collect();
+        let ruleset1 = count_nice(RULESET1, &lowercase, 300).unwrap();
+        assert!(ruleset1 > BigUint::ZERO && ruleset1 < BigUint::from(26u8).pow(300));
+        // From 704 letters on every string repeats a pair, and the strings that
+        // fail rule5 pick any two letters, then one of 25 for each letter after.
+        let ruleset2 = count_nice(RULESET2, &lowercase, 704).unwrap();
+        assert_eq!(ruleset2, BigUint::from(26u8).pow(704) - BigUint::from(676u16) * BigUint::from(25u8).pow(702));
+        assert!(count_nice(RULESET2, &lowercase, 703).is_err());
+        assert!(count_nice(RULESET1 | RULESET2, &lowercase, 4).is_err());
+    }
+}
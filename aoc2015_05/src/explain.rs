@@ -0,0 +1,144 @@
+// Diagnostic versions of rule1..rule5: each reports whether the rule holds and
+// the evidence behind the verdict, so classifications can be audited.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum Evidence {
+    Vowels(usize),
+    DoubleLetter { letter: char, offset: usize },
+    NoDoubleLetter,
+    ForbiddenPair { pair: (char, char), offset: usize },
+    NoForbiddenPair,
+    RepeatedPair { pair: (char, char), first: usize, second: usize },
+    NoRepeatedPair,
+    GapRepeat { letter: char, offset: usize },
+    NoGapRepeat,
+}
+
+pub type Explainer = fn(&str) -> Finding;
+
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub passed: bool,
+    pub evidence: Evidence,
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Evidence::Vowels(count) => write!(f, "{} vowels (3 required)", count),
+            Evidence::DoubleLetter { letter, offset } => write!(f, "'{}{}' at offset {}", letter, letter, offset),
+            Evidence::NoDoubleLetter => write!(f, "no letter appears twice in a row"),
+            Evidence::ForbiddenPair { pair, offset } => write!(f, "forbidden '{}{}' at offset {}", pair.0, pair.1, offset),
+            Evidence::NoForbiddenPair => write!(f, "none of ab, cd, pq, xy"),
+            Evidence::RepeatedPair { pair, first, second } =>
+                write!(f, "'{}{}' at offsets {} and {}", pair.0, pair.1, first, second),
+            Evidence::NoRepeatedPair => write!(f, "no pair appears twice without overlapping"),
+            Evidence::GapRepeat { letter, offset } => write!(f, "'{}' at offsets {} and {}", letter, offset, offset + 2),
+            Evidence::NoGapRepeat => write!(f, "no letter repeats with one letter between"),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.rule, if self.passed { "ok" } else { "FAILED" }, self.evidence)
+    }
+}
+
+pub fn explain_rule1(s: &str) -> Finding {
+    let count = s.chars().filter(|c| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')).count();
+    Finding { rule: "rule1", passed: count >= 3, evidence: Evidence::Vowels(count) }
+}
+
+pub fn explain_rule2(s: &str) -> Finding {
+    let chars: Vec<char> = s.chars().collect();
+    match chars.windows(2).position(|w| w[0] == w[1]) {
+        Some(offset) => Finding { rule: "rule2", passed: true, evidence: Evidence::DoubleLetter { letter: chars[offset], offset } },
+        None => Finding { rule: "rule2", passed: false, evidence: Evidence::NoDoubleLetter },
+    }
+}
+
+pub fn explain_rule3(s: &str) -> Finding {
+    let chars: Vec<char> = s.chars().collect();
+    let forbidden = chars.windows(2)
+        .position(|w| matches!((w[0], w[1]), ('a', 'b') | ('c', 'd') | ('p', 'q') | ('x', 'y')));
+    match forbidden {
+        Some(offset) => Finding {
+            rule: "rule3",
+            passed: false,
+            evidence: Evidence::ForbiddenPair { pair: (chars[offset], chars[offset + 1]), offset },
+        },
+        None => Finding { rule: "rule3", passed: true, evidence: Evidence::NoForbiddenPair },
+    }
+}
+
+// Same scan as rule4, keeping the offset of every pair seen so far.
+pub fn explain_rule4(s: &str) -> Finding {
+    let chars: Vec<char> = s.chars().collect();
+    let mut pairs: Vec<((char, char), usize)> = Vec::new();
+    for (offset, w) in chars.windows(2).enumerate() {
+        let pair = (w[0], w[1]);
+        if let Some(&(_, first)) = pairs.iter().find(|&&(p, first)| p == pair && first + 2 <= offset) {
+            return Finding { rule: "rule4", passed: true, evidence: Evidence::RepeatedPair { pair, first, second: offset } };
+        }
+        pairs.push((pair, offset));
+    }
+    Finding { rule: "rule4", passed: false, evidence: Evidence::NoRepeatedPair }
+}
+
+pub fn explain_rule5(s: &str) -> Finding {
+    let chars: Vec<char> = s.chars().collect();
+    match chars.windows(3).position(|w| w[0] == w[2]) {
+        Some(offset) => Finding { rule: "rule5", passed: true, evidence: Evidence::GapRepeat { letter: chars[offset], offset } },
+        None => Finding { rule: "rule5", passed: false, evidence: Evidence::NoGapRepeat },
+    }
+}
+
+pub fn explain_ruleset(s: &str, rules: &[Explainer]) -> Vec<Finding> {
+    rules.iter().map(|rule| rule(s)).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{rule1, rule2, rule3, rule4, rule5};
+
+    #[test]
+    fn test_findings_agree_with_rules()
+    {
+        let samples = ["ugknbfddgicrmopn", "aaa", "jchzalrnumimnmhp", "haegwjzuvuyypxyu", "dvszwmarrgswjxmb",
+                       "qjhvhtzxzqqjkmpb", "xxyxx", "uurcxstgmygtbstg", "ieodomkazucvgmuy", "ueihvxviirnooomi", "aaaa", "aaab"];
+        for s in samples {
+            assert_eq!(explain_rule1(s).passed, rule1(s), "{}", s);
+            assert_eq!(explain_rule2(s).passed, rule2(s), "{}", s);
+            assert_eq!(explain_rule3(s).passed, rule3(s), "{}", s);
+            assert_eq!(explain_rule4(s).passed, rule4(s), "{}", s);
+            assert_eq!(explain_rule5(s).passed, rule5(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_naughty_evidence()
+    {
+        let findings = explain_ruleset("haegwjzuvuyypxyu", &[explain_rule1, explain_rule2, explain_rule3]);
+        assert!(findings[0].passed && findings[1].passed);
+        assert_eq!(findings[2].evidence, Evidence::ForbiddenPair { pair: ('x', 'y'), offset: 13 });
+        assert_eq!(findings[2].to_string(), "rule3 FAILED: forbidden 'xy' at offset 13");
+
+        assert_eq!(explain_rule1("dvszwmarrgswjxmb").evidence, Evidence::Vowels(1));
+        assert_eq!(explain_rule5("uurcxstgmygtbstg").evidence, Evidence::NoGapRepeat);
+    }
+
+    #[test]
+    fn test_nice_witnesses()
+    {
+        assert_eq!(explain_rule2("ugknbfddgicrmopn").evidence, Evidence::DoubleLetter { letter: 'd', offset: 6 });
+        assert_eq!(explain_rule4("qjhvhtzxzqqjkmpb").evidence, Evidence::RepeatedPair { pair: ('q', 'j'), first: 0, second: 10 });
+        assert_eq!(explain_rule4("aaaa").evidence, Evidence::RepeatedPair { pair: ('a', 'a'), first: 0, second: 2 });
+        assert_eq!(explain_rule5("qjhvhtzxzqqjkmpb").evidence, Evidence::GapRepeat { letter: 'h', offset: 2 });
+    }
+}
@@ -0,0 +1,254 @@
+// Builds random strings that satisfy a chosen combination of rule1..rule5 and
+// their negations. The string grows one letter at a time with backtracking:
+// properties a prefix can only gain (vowels >= 3, a double letter, a forbidden
+// pair, a repeated pair, a gap repeat) prune immediately when they must not
+// hold, and a length check prunes prefixes that can no longer gain the ones
+// that must. Every verdict comes from the reference rule functions.
+
+use crate::{rule1, rule2, rule3, rule4, rule5, RuleFn};
+
+const RULES: [RuleFn; 5] = [rule1, rule2, rule3, rule4, rule5];
+const NODE_BUDGET: usize = 20_000;
+const RESTARTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Requirement {
+    Any,
+    Holds,
+    Fails,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spec {
+    pub rules: [Requirement; 5],
+}
+
+impl Spec
+{
+    // "1,2,!3" requires rule1 and rule2 and forbids rule3; "rule4,!rule5" also works.
+    pub fn parse(text: &str) -> Result<Spec, String> {
+        let mut rules = [Requirement::Any; 5];
+        for token in text.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let (requirement, name) = match token.strip_prefix('!') {
+                Some(name) => (Requirement::Fails, name),
+                None => (Requirement::Holds, token),
+            };
+            let index = name.trim_start_matches("rule").parse::<usize>().ok()
+                .filter(|n| (1..=5).contains(n))
+                .ok_or(format!("Unknown rule '{}'", token))?;
+            rules[index - 1] = requirement;
+        }
+        Ok(Spec { rules })
+    }
+
+    pub fn accepts(&self, s: &str) -> bool {
+        self.rules.iter().zip(RULES).all(|(requirement, rule)| match requirement {
+            Requirement::Any => true,
+            Requirement::Holds => rule(s),
+            Requirement::Fails => !rule(s),
+        })
+    }
+
+    fn requires(&self, rule: usize, requirement: Requirement) -> bool {
+        self.rules[rule - 1] == requirement
+    }
+}
+
+// Xorshift64, enough for fixtures and reproducible from a seed.
+pub struct Rng(u64);
+
+impl Rng
+{
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// Words with no constraint at all, for differential tests and benchmarks.
+pub fn random_words(rng: &mut Rng, count: usize, len: usize, alphabet: &str) -> Vec<String>
+{
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    (0..count).map(|_| (0..len).map(|_| alphabet[rng.below(alphabet.len())]).collect()).collect()
+}
+
+struct Search<'a> {
+    spec: &'a Spec,
+    len: usize,
+    rng: &'a mut Rng,
+    nodes: usize,
+}
+
+impl Search<'_>
+{
+    // True once the prefix gained a property it must never have.
+    fn prefix_violates(&self, prefix: &str) -> bool {
+        let spec = self.spec;
+        (spec.requires(1, Requirement::Fails) && rule1(prefix))
+            || (spec.requires(2, Requirement::Fails) && rule2(prefix))
+            || (spec.requires(3, Requirement::Holds) && !rule3(prefix))
+            || (spec.requires(4, Requirement::Fails) && rule4(prefix))
+            || (spec.requires(5, Requirement::Fails) && rule5(prefix))
+    }
+
+    // Lower bound on the letters still needed to gain every required property.
+    fn letters_needed(&self, prefix: &str) -> usize {
+        let spec = self.spec;
+        let p = prefix.len();
+        let vowels = prefix.chars().filter(|c| "aeiou".contains(*c)).count();
+        let mut needed = 0;
+        if spec.requires(1, Requirement::Holds) {
+            needed = needed.max(3usize.saturating_sub(vowels));
+        }
+        if spec.requires(2, Requirement::Holds) && !rule2(prefix) {
+            needed = needed.max(2usize.saturating_sub(p).max(1));
+        }
+        if spec.requires(3, Requirement::Fails) && rule3(prefix) {
+            needed = needed.max(2usize.saturating_sub(p).max(1));
+        }
+        if spec.requires(4, Requirement::Holds) && !rule4(prefix) {
+            needed = needed.max(4usize.saturating_sub(p).max(1));
+        }
+        if spec.requires(5, Requirement::Holds) && !rule5(prefix) {
+            needed = needed.max(3usize.saturating_sub(p).max(1));
+        }
+        needed
+    }
+
+    fn candidates(&mut self, prefix: &[u8]) -> Vec<u8> {
+        let mut letters: Vec<u8> = (b'a'..=b'z').collect();
+        for i in (1..letters.len()).rev() {
+            let j = self.rng.below(i + 1);
+            letters.swap(i, j);
+        }
+        // Letters that would complete a required property go first half of the time.
+        if self.rng.below(2) == 0 {
+            let mut helpful = Vec::new();
+            if let Some(&prev) = prefix.last() {
+                helpful.push(prev);
+                let forbidden_next = match prev { b'a' => Some(b'b'), b'c' => Some(b'd'), b'p' => Some(b'q'), b'x' => Some(b'y'), _ => None };
+                if self.spec.requires(3, Requirement::Fails) {
+                    helpful.extend(forbidden_next);
+                }
+                helpful.extend(prefix.windows(2).rev().skip(1).filter(|w| w[0] == prev).map(|w| w[1]));
+            }
+            if prefix.len() >= 2 {
+                helpful.push(prefix[prefix.len() - 2]);
+            }
+            helpful.push(b"aeiou"[self.rng.below(5)]);
+            let start = self.rng.below(helpful.len());
+            let pick = helpful[start];
+            letters.retain(|&c| c != pick);
+            letters.insert(0, pick);
+        }
+        letters
+    }
+
+    fn extend(&mut self, prefix: &mut Vec<u8>) -> bool {
+        self.nodes += 1;
+        if self.nodes > NODE_BUDGET {
+            return false;
+        }
+        let text = std::str::from_utf8(prefix).unwrap();
+        if self.prefix_violates(text) || self.letters_needed(text) > self.len - prefix.len() {
+            return false;
+        }
+        if prefix.len() == self.len {
+            return self.spec.accepts(text);
+        }
+        for c in self.candidates(prefix) {
+            prefix.push(c);
+            if self.extend(prefix) {
+                return true;
+            }
+            prefix.pop();
+        }
+        false
+    }
+}
+
+// None when no string of that length could be found: either the combination
+// is impossible at that length or every restart ran out of search budget.
+pub fn generate(spec: &Spec, len: usize, rng: &mut Rng) -> Option<String>
+{
+    for _ in 0..RESTARTS {
+        let mut search = Search { spec, len, rng: &mut *rng, nodes: 0 };
+        let mut prefix = Vec::with_capacity(len);
+        if search.extend(&mut prefix) {
+            return Some(String::from_utf8(prefix).unwrap());
+        }
+        if search.nodes <= NODE_BUDGET {
+            // The whole tree was explored without success.
+            return None;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::automaton::*;
+
+    fn all_specs() -> Vec<Spec> {
+        let choices = [Requirement::Any, Requirement::Holds, Requirement::Fails];
+        (0..3usize.pow(5))
+            .map(|mut n| Spec { rules: std::array::from_fn(|_| { let r = choices[n % 3]; n /= 3; r }) })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_spec()
+    {
+        let spec = Spec::parse("1, !rule3,5").unwrap();
+        assert_eq!(spec.rules, [Requirement::Holds, Requirement::Any, Requirement::Fails, Requirement::Any, Requirement::Holds]);
+        assert!(Spec::parse("6").is_err());
+        assert!(Spec::parse("!x").is_err());
+    }
+
+    #[test]
+    fn test_every_combination()
+    {
+        let mut rng = Rng::new(42);
+        for spec in all_specs() {
+            let s = generate(&spec, 16, &mut rng).unwrap_or_else(|| panic!("No string for {:?}", spec));
+            assert_eq!(s.len(), 16);
+            assert!(spec.accepts(&s), "{} for {:?}", s, spec);
+        }
+    }
+
+    #[test]
+    fn test_impossible_lengths()
+    {
+        let mut rng = Rng::new(7);
+        assert_eq!(generate(&Spec::parse("1").unwrap(), 2, &mut rng), None);
+        assert_eq!(generate(&Spec::parse("4").unwrap(), 3, &mut rng), None);
+        assert!(generate(&Spec::parse("4").unwrap(), 4, &mut rng).is_some());
+    }
+
+    #[test]
+    fn test_fuzz_automaton_against_reference()
+    {
+        let reference: [(u8, RuleFn); 5] = [(RULE1, rule1), (RULE2, rule2), (RULE3, rule3), (RULE4, rule4), (RULE5, rule5)];
+        let mut rng = Rng::new(2015);
+        for spec in all_specs() {
+            for len in [9, 24] {
+                let Some(s) = generate(&spec, len, &mut rng) else { continue };
+                for (rule, reference) in reference {
+                    assert_eq!(Automaton::new(rule).evaluate(&s), reference(&s), "rule {:b} on {}", rule, s);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+pub mod automaton;
+pub mod count;
+pub mod explain;
+pub mod generate;
+pub mod rules;
+pub mod stream;
+
+use std::collections::VecDeque;
+
+pub type RuleFn = fn(&str) -> bool;
+
+pub fn rule1 (s: &str) -> bool {
+    let mut count = 0;
+    for c in s.chars() {
+        match c {
+            'a' | 'e' | 'i' | 'o' | 'u' => count += 1,
+            _ => (),
+        }
+    }
+    count >= 3
+}
+
+pub fn rule2 (s: &str) -> bool {
+    let mut prev = ' ';
+    for c in s.chars() {
+        if c == prev {
+            return true;
+        }
+        prev = c;
+    }
+    false
+}
+
+pub fn rule3 (s: &str) -> bool {
+    let mut prev = ' ';
+    for c in s.chars() {
+        match (prev, c) {
+            ('a', 'b') | ('c', 'd') | ('p', 'q') | ('x', 'y') => return false,
+            _ => (),
+        }
+        prev = c;
+    }
+    true
+}
+
+pub fn rule4 (s: &str) -> bool {
+    let mut chars = s.chars();
+    let Some(mut prev) = chars.next() else {
+        return false;
+    };
+    let mut pairs = Vec::new();
+    for c in chars {
+        // The last pair recorded overlaps the current one, any earlier one does not.
+        if pairs.len() > 1 && pairs[..pairs.len() - 1].contains(&(prev,c)) {
+            return true;
+        }
+        pairs.push((prev,c));  
+        prev = c;
+    }
+    false
+}
+
+pub fn rule5 (s: &str) -> bool {
+    let mut chars = s.chars();
+    let (Some(first), Some(second)) = (chars.next(), chars.next()) else {
+        return false;
+    };
+    let mut buffer = VecDeque::new();
+    buffer.push_back(first);
+    buffer.push_back(second);
+    
+    for c in chars {
+        buffer.push_back(c);
+        if buffer.front() == buffer.back() {
+            return true;
+        }
+        buffer.pop_front();
+    }
+    false
+}
+
+pub fn apply_ruleset(s: &str, rules: &[RuleFn]) -> bool {
+    for rule in rules {
+        if !rule(s) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_ruleset1_1()
+    {
+        let ruleset = [rule1, rule2, rule3];
+        const DATA: &str = "ugknbfddgicrmopn";
+        assert!(apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset1_2()
+    {
+        let ruleset = [rule1, rule2, rule3];
+        const DATA: &str = "aaa";
+        assert!(apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset1_3()
+    {
+        let ruleset = [rule1, rule2, rule3];
+        const DATA: &str = "jchzalrnumimnmhp";
+        assert!(!apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset1_4()
+    {
+        let ruleset = [rule1, rule2, rule3];
+        const DATA: &str = "haegwjzuvuyypxyu";
+        assert!(!apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset1_5()
+    {
+        let ruleset = [rule1, rule2, rule3];
+        const DATA: &str = "dvszwmarrgswjxmb";
+        assert!(!apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset2_1()
+    {
+        let ruleset = [rule4, rule5];
+        const DATA: &str = "qjhvhtzxzqqjkmpb";
+        assert!(apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset2_2()
+    {
+        let ruleset = [rule4, rule5];
+        const DATA: &str = "xxyxx";
+        assert!(apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_ruleset2_3()
+    {
+        let ruleset = [rule4, rule5];
+        const DATA: &str = "uurcxstgmygtbstg";
+        assert!(!apply_ruleset(DATA, &ruleset));
+    }    
+    #[test]
+    fn test_ruleset2_4()
+    {
+        let ruleset = [rule4, rule5];
+        const DATA: &str = "ieodomkazucvgmuy";
+        assert!(!apply_ruleset(DATA, &ruleset));
+    }
+    #[test]
+    fn test_rule4()
+    {
+        const DATA: &str = "ueihvxviirnooomi";
+        assert!(!rule4(DATA));
+    }
+    #[test]
+    fn test_rule4_run_of_four()
+    {
+        // "aaaa" has "aa" at 0 and 2, which do not overlap.
+        assert!(rule4("aaaa"));
+        assert!(rule4("xaaaay"));
+        assert!(!rule4("aaa"));
+    }
+    #[test]
+    fn test_short_strings()
+    {
+        for s in ["", "a", "é", "€", "ba"] {
+            assert!(!rule1(s) && !rule2(s) && rule3(s) && !rule4(s) && !rule5(s), "{}", s);
+        }
+    }
+    #[test]
+    fn test_non_ascii()
+    {
+        assert!(rule1("aéeïo"));
+        assert!(!rule1("éèïöü"));
+        assert!(rule2("xééy"));
+        assert!(rule4("€ü€ü"));
+        assert!(rule5("€x€"));
+        assert!(!rule5("€€"));
+    }
+
+}
\ No newline at end of file
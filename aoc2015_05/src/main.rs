@@ -1,175 +1,145 @@
-use std::collections::VecDeque;
 use std::env;
+use std::process;
 use std::fs::*;
 use std::io::*;
+use std::thread;
 
-fn rule1 (s: &str) -> bool {
-    let mut count = 0;
-    for c in s.chars() {
-        match c {
-            'a' | 'e' | 'i' | 'o' | 'u' => count += 1,
-            _ => (),
-        }
-    }
-    count >= 3
+use aoc2015_05::automaton::{Alphabet, Automaton, RULESET1, RULESET2};
+use aoc2015_05::count::{count_nice, LOWERCASE};
+use aoc2015_05::explain::*;
+use aoc2015_05::generate::{generate, Rng, Spec};
+use aoc2015_05::rules::Rule;
+use aoc2015_05::stream::{classify_stream, StreamOptions};
+
+const LINES_PER_THREAD: usize = 4096;
+
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let idx = args.iter().position(|a| a == name)?;
+    Some(args.get(idx + 1).unwrap_or_else(|| panic!("{} expects a value", name)))
 }
 
-fn rule2 (s: &str) -> bool {
-    let mut prev = ' ';
-    for c in s.chars() {
-        if c == prev {
-            return true;
-        }
-        prev = c;
+fn output(path: Option<&str>, default: Box<dyn Write>) -> Box<dyn Write> {
+    match path {
+        Some(path) => Box::new(BufWriter::new(File::create(path).expect("Failed to create output file"))),
+        None => default,
     }
-    false
 }
 
-fn rule3 (s: &str) -> bool {
-    let mut prev = ' ';
-    for c in s.chars() {
-        match (prev, c) {
-            ('a', 'b') | ('c', 'd') | ('p', 'q') | ('x', 'y') => return false,
-            _ => (),
-        }
-        prev = c;
+fn ruleset(args: &[String]) -> u8 {
+    match arg_value(args, "--ruleset") {
+        None | Some("1") => RULESET1,
+        Some("2") => RULESET2,
+        Some(other) => panic!("Unknown ruleset: {}", other),
     }
-    true
 }
 
-fn rule4 (s: &str) -> bool {
-    let mut prevprev = ' ';
-    let mut prev = s.chars().nth(0).unwrap();
-    let mut pairs = Vec::new();
-    for c in s.chars().skip(1) {
-        if pairs.contains(&(prev,c)) && ((prevprev , prev) != (prev, c)) {
-            return true;
-        }
-        pairs.push((prev,c));  
-        prevprev = prev;
-        prev = c;
-    }
-    false
+// Streams the input (or stdin for "-") without loading it, writing nice strings
+// to --nice (default stdout) and naughty ones to --naughty (default discarded).
+fn stream(args: &[String], alphabet: Alphabet)
+{
+    let rules = ruleset(args);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let options = StreamOptions { rules, alphabet, threads, lines_per_thread: LINES_PER_THREAD };
+
+    let input: Box<dyn BufRead> = match args[1].as_str() {
+        "-" => Box::new(stdin().lock()),
+        path => Box::new(BufReader::new(File::open(path).expect("Failed to open file"))),
+    };
+    let mut nice = output(arg_value(args, "--nice"), Box::new(BufWriter::new(stdout().lock())));
+    let mut naughty = output(arg_value(args, "--naughty"), Box::new(sink()));
+
+    let counts = classify_stream(input, &options, &mut nice, &mut naughty).expect("Failed to classify input");
+    eprintln!("Nice: {}, naughty: {}", counts.nice, counts.naughty);
 }
 
-fn rule5 (s: &str) -> bool {
-    if s.len() < 3 {
-        return false;
-    }
-    let mut buffer = VecDeque::new();
-    buffer.push_back(s.chars().nth(0).unwrap());
-    buffer.push_back(s.chars().nth(1).unwrap());
-    
-    for c in s.chars().skip(2) {
-        buffer.push_back(c);
-        if buffer.front() == buffer.back() {
-            return true;
+// Prints --count strings of --length letters matching a spec such as "1,2,!3".
+fn generate_strings(args: &[String], spec: &str)
+{
+    let spec = Spec::parse(spec).unwrap_or_else(|e| panic!("Invalid spec: {}", e));
+    let len = arg_value(args, "--length").map_or(16, |n| n.parse().expect("--length expects a number"));
+    let count = arg_value(args, "--count").map_or(1, |n| n.parse().expect("--count expects a number"));
+    let seed = arg_value(args, "--seed").map_or(2015, |n| n.parse().expect("--seed expects a number"));
+
+    let mut rng = Rng::new(seed);
+    for _ in 0..count {
+        match generate(&spec, len, &mut rng) {
+            Some(s) => println!("{}", s),
+            None => panic!("No string of length {} satisfies the spec", len),
         }
-        buffer.pop_front();
     }
-    false
 }
 
-fn apply_ruleset(s: &str, rules: &[fn(&str) -> bool]) -> bool {
-    for rule in rules {
-        if !rule(s) {
-            return false;
+// Prints how many strings of the given length over a..z are nice under --ruleset.
+fn count_strings(args: &[String], len: &str)
+{
+    let len = len.parse().expect("--count-nice expects a number");
+    let letters: Vec<char> = LOWERCASE.chars().collect();
+    match count_nice(ruleset(args), &letters, len) {
+        Ok(count) => println!("{}", count),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
         }
     }
-    true
 }
+
 fn main() 
 {
     let args: Vec<String> = env::args().collect();
+    if let Some(len) = arg_value(&args, "--count-nice") {
+        count_strings(&args, len);
+        return;
+    }
+    if let Some(spec) = arg_value(&args, "--generate") {
+        generate_strings(&args, spec);
+        return;
+    }
     assert!((args.len() > 1), "Missing input file argument");
 
+    let alphabet = match arg_value(&args, "--alphabet") {
+        None | Some("chars") => Alphabet::Chars,
+        Some("graphemes") => Alphabet::Graphemes,
+        Some(other) => panic!("Unknown alphabet: {}", other),
+    };
+    if args.iter().any(|a| a == "--stream") {
+        stream(&args, alphabet);
+        return;
+    }
+
     let file = File::open(&args[1]).unwrap();
     let lines: Vec<_> = BufReader::new(file)
         .lines()
-        .filter_map(Result::ok)
+        .map_while(Result::ok)
         .collect();
 
-   
-    let silver = lines.iter().map(|line|apply_ruleset(line.as_str(), &[rule1, rule2, rule3])).filter(| x | *x).count();
+
+    let mut ruleset1 = Automaton::new(RULESET1);
+    let silver = lines.iter().filter(|line| ruleset1.evaluate_with(line, alphabet)).count();
     println!("Silver: {}", silver);
 
-    let gold = lines.iter().map(|line|apply_ruleset(line.as_str(), &[rule4, rule5])).filter(| x | *x).count();
+    let mut ruleset2 = Automaton::new(RULESET2);
+    let gold = lines.iter().filter(|line| ruleset2.evaluate_with(line, alphabet)).count();
     println!("Gold: {}", gold);
-}
 
-#[cfg(test)]
-mod tests
-{
-    use super::*;
-
-    #[test]
-    fn test_ruleset1_1()
-    {
-        let ruleset = [rule1, rule2, rule3];
-        const DATA: &str = "ugknbfddgicrmopn";
-        assert!(apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset1_2()
-    {
-        let ruleset = [rule1, rule2, rule3];
-        const DATA: &str = "aaa";
-        assert!(apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset1_3()
-    {
-        let ruleset = [rule1, rule2, rule3];
-        const DATA: &str = "jchzalrnumimnmhp";
-        assert!(!apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset1_4()
-    {
-        let ruleset = [rule1, rule2, rule3];
-        const DATA: &str = "haegwjzuvuyypxyu";
-        assert!(!apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset1_5()
-    {
-        let ruleset = [rule1, rule2, rule3];
-        const DATA: &str = "dvszwmarrgswjxmb";
-        assert!(!apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset2_1()
-    {
-        let ruleset = [rule4, rule5];
-        const DATA: &str = "qjhvhtzxzqqjkmpb";
-        assert!(apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset2_2()
-    {
-        let ruleset = [rule4, rule5];
-        const DATA: &str = "xxyxx";
-        assert!(apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_ruleset2_3()
-    {
-        let ruleset = [rule4, rule5];
-        const DATA: &str = "uurcxstgmygtbstg";
-        assert!(!apply_ruleset(DATA, &ruleset));
-    }    
-    #[test]
-    fn test_ruleset2_4()
-    {
-        let ruleset = [rule4, rule5];
-        const DATA: &str = "ieodomkazucvgmuy";
-        assert!(!apply_ruleset(DATA, &ruleset));
-    }
-    #[test]
-    fn test_rule4()
-    {
-        const DATA: &str = "ueihvxviirnooomi";
-        assert!(!rule4(DATA));
+    if let Some(path) = arg_value(&args, "--rules") {
+        let policy = Rule::parse_policy(&std::fs::read_to_string(path).expect("Failed to read policy file"))
+            .unwrap_or_else(|e| panic!("Invalid policy: {}", e));
+        let nice = lines.iter().filter(|line| policy.matches(line)).count();
+        println!("Policy: {}", nice);
     }
 
-}
\ No newline at end of file
+    if args.iter().any(|a| a == "--explain") {
+        let rulesets: [(&str, &[Explainer]); 2] = [
+            ("ruleset 1", &[explain_rule1, explain_rule2, explain_rule3]),
+            ("ruleset 2", &[explain_rule4, explain_rule5]),
+        ];
+        for line in &lines {
+            for (name, rules) in rulesets {
+                let findings = explain_ruleset(line, rules);
+                let nice = findings.iter().all(|f| f.passed);
+                println!("{} {} under {}", line, if nice { "nice" } else { "naughty" }, name);
+                findings.iter().for_each(|f| println!("    {}", f));
+            }
+        }
+    }
+}
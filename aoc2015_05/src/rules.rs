@@ -0,0 +1,223 @@
+// A small language for nice string policies. A policy file holds one rule
+// expression per line, all of which must hold; blank lines and lines starting
+// with '#' are ignored. Expressions combine atoms with NOT, AND and OR (in
+// decreasing precedence) and parentheses:
+//
+//   vowels >= 3
+//   double_letter
+//   forbid ab,cd,pq,xy
+//   repeat_pair_nonoverlap
+//   gap_repeat 1
+//   NOT forbid xyz OR (vowels < 2 AND gap_repeat 0)
+
+use crate::{rule2, rule4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison
+{
+    fn parse(s: &str) -> Option<Comparison> {
+        match s {
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessOrEqual),
+            "==" => Some(Comparison::Equal),
+            ">=" => Some(Comparison::GreaterOrEqual),
+            ">" => Some(Comparison::Greater),
+            _ => None,
+        }
+    }
+
+    fn holds(&self, lhs: usize, rhs: usize) -> bool {
+        match self {
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Equal => lhs == rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+            Comparison::Greater => lhs > rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Vowels(Comparison, usize),
+    DoubleLetter,
+    Forbid(Vec<String>),
+    RepeatPairNonOverlap,
+    GapRepeat(usize),
+    Not(Box<Rule>),
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+}
+
+impl Rule
+{
+    pub fn matches(&self, s: &str) -> bool {
+        match self {
+            Rule::Vowels(cmp, n) => cmp.holds(s.chars().filter(|c| "aeiou".contains(*c)).count(), *n),
+            Rule::DoubleLetter => rule2(s),
+            Rule::Forbid(patterns) => !patterns.iter().any(|p| s.contains(p.as_str())),
+            Rule::RepeatPairNonOverlap => rule4(s),
+            Rule::GapRepeat(gap) => {
+                let chars: Vec<char> = s.chars().collect();
+                chars.windows(gap + 2).any(|w| w[0] == w[gap + 1])
+            }
+            Rule::Not(rule) => !rule.matches(s),
+            Rule::And(rules) => rules.iter().all(|r| r.matches(s)),
+            Rule::Or(rules) => rules.iter().any(|r| r.matches(s)),
+        }
+    }
+
+    pub fn parse(expression: &str) -> Result<Rule, String> {
+        let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut parser = Parser { tokens, pos: 0 };
+        let rule = parser.expression()?;
+        match parser.peek() {
+            None => Ok(rule),
+            Some(token) => Err(format!("Unexpected '{}' in '{}'", token, expression)),
+        }
+    }
+
+    pub fn parse_policy(text: &str) -> Result<Rule, String> {
+        let rules = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Rule::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Rule::And(rules))
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a>
+{
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.peek().ok_or("Unexpected end of rule")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.next()?;
+        token.parse::<usize>().map_err(|_| format!("Expected a number, found '{}'", token))
+    }
+
+    fn expression(&mut self) -> Result<Rule, String> {
+        let mut terms = vec![self.term()?];
+        while self.peek() == Some("OR") {
+            self.pos += 1;
+            terms.push(self.term()?);
+        }
+        Ok(if terms.len() == 1 { terms.pop().unwrap() } else { Rule::Or(terms) })
+    }
+
+    fn term(&mut self) -> Result<Rule, String> {
+        let mut factors = vec![self.factor()?];
+        while self.peek() == Some("AND") {
+            self.pos += 1;
+            factors.push(self.factor()?);
+        }
+        Ok(if factors.len() == 1 { factors.pop().unwrap() } else { Rule::And(factors) })
+    }
+
+    fn factor(&mut self) -> Result<Rule, String> {
+        match self.next()? {
+            "NOT" => Ok(Rule::Not(Box::new(self.factor()?))),
+            "(" => {
+                let rule = self.expression()?;
+                match self.next()? {
+                    ")" => Ok(rule),
+                    token => Err(format!("Expected ')', found '{}'", token)),
+                }
+            }
+            "vowels" => {
+                let op = self.next()?;
+                let cmp = Comparison::parse(op).ok_or(format!("Unknown comparison '{}'", op))?;
+                Ok(Rule::Vowels(cmp, self.number()?))
+            }
+            "double_letter" => Ok(Rule::DoubleLetter),
+            "forbid" => {
+                let list = self.next()?;
+                Ok(Rule::Forbid(list.split(',').filter(|p| !p.is_empty()).map(String::from).collect()))
+            }
+            "repeat_pair_nonoverlap" => Ok(Rule::RepeatPairNonOverlap),
+            "gap_repeat" => Ok(Rule::GapRepeat(self.number()?)),
+            token => Err(format!("Unknown rule '{}'", token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{apply_ruleset, rule1, rule3, rule5};
+
+    const SILVER_POLICY: &str = "vowels >= 3\ndouble_letter\nforbid ab,cd,pq,xy\n";
+    const GOLD_POLICY: &str = "# ruleset 2\nrepeat_pair_nonoverlap\n\ngap_repeat 1\n";
+    const SAMPLES: [&str; 9] = ["ugknbfddgicrmopn", "aaa", "jchzalrnumimnmhp", "haegwjzuvuyypxyu", "dvszwmarrgswjxmb",
+                                "qjhvhtzxzqqjkmpb", "xxyxx", "uurcxstgmygtbstg", "ieodomkazucvgmuy"];
+
+    #[test]
+    fn test_policies_match_rulesets()
+    {
+        let silver = Rule::parse_policy(SILVER_POLICY).unwrap();
+        let gold = Rule::parse_policy(GOLD_POLICY).unwrap();
+        for s in SAMPLES {
+            assert_eq!(silver.matches(s), apply_ruleset(s, &[rule1, rule2, rule3]), "{}", s);
+            assert_eq!(gold.matches(s), apply_ruleset(s, &[rule4, rule5]), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence()
+    {
+        let rule = Rule::parse("NOT double_letter OR vowels == 1 AND gap_repeat 2").unwrap();
+        assert_eq!(rule, Rule::Or(vec![
+            Rule::Not(Box::new(Rule::DoubleLetter)),
+            Rule::And(vec![Rule::Vowels(Comparison::Equal, 1), Rule::GapRepeat(2)]),
+        ]));
+    }
+
+    #[test]
+    fn test_combinators()
+    {
+        let rule = Rule::parse("NOT (forbid xyz OR vowels < 2)").unwrap();
+        assert!(rule.matches("axyzeb"));
+        assert!(!rule.matches("aeb"));
+        assert!(!rule.matches("xyz"));
+    }
+
+    #[test]
+    fn test_gap_repeat()
+    {
+        assert!(Rule::GapRepeat(0).matches("abba"));
+        assert!(Rule::GapRepeat(2).matches("abca"));
+        assert!(!Rule::GapRepeat(2).matches("abc"));
+    }
+
+    #[test]
+    fn test_parse_errors()
+    {
+        assert!(Rule::parse("vowels >= many").is_err());
+        assert!(Rule::parse("(double_letter").is_err());
+        assert!(Rule::parse("double_letter extra").is_err());
+        assert!(Rule::parse("shiny").is_err());
+    }
+}
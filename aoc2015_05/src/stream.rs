@@ -0,0 +1,95 @@
+// Classifies arbitrarily large word lists: lines are read lazily in batches,
+// each batch is split across worker threads, and the verdicts are written back
+// in input order.
+
+use std::io::{self, BufRead, Write};
+use std::thread;
+
+use crate::automaton::{Alphabet, Automaton};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Counts {
+    pub nice: usize,
+    pub naughty: usize,
+}
+
+pub struct StreamOptions {
+    pub rules: u8,
+    pub alphabet: Alphabet,
+    pub threads: usize,
+    pub lines_per_thread: usize,
+}
+
+fn classify_batch(lines: &[String], options: &StreamOptions) -> Vec<bool> {
+    let chunk_size = lines.len().div_ceil(options.threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = lines.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || {
+                let mut automaton = Automaton::new(options.rules);
+                chunk.iter().map(|line| automaton.evaluate_with(line, options.alphabet)).collect::<Vec<_>>()
+            }))
+            .collect();
+        workers.into_iter().flat_map(|w| w.join().expect("Classifier thread panicked")).collect()
+    })
+}
+
+pub fn classify_stream<R: BufRead>(input: R, options: &StreamOptions, nice: &mut dyn Write, naughty: &mut dyn Write) -> io::Result<Counts>
+{
+    let batch_size = options.threads.max(1) * options.lines_per_thread.max(1);
+    let mut lines = input.lines();
+    let mut counts = Counts::default();
+
+    loop {
+        let batch = lines.by_ref().take(batch_size).collect::<io::Result<Vec<String>>>()?;
+        if batch.is_empty() {
+            break;
+        }
+        for (line, is_nice) in batch.iter().zip(classify_batch(&batch, options)) {
+            if is_nice {
+                counts.nice += 1;
+                writeln!(nice, "{}", line)?;
+            } else {
+                counts.naughty += 1;
+                writeln!(naughty, "{}", line)?;
+            }
+        }
+    }
+
+    nice.flush()?;
+    naughty.flush()?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::automaton::{RULESET1, RULESET2};
+
+    const SAMPLES: &str = "ugknbfddgicrmopn\naaa\njchzalrnumimnmhp\nhaegwjzuvuyypxyu\ndvszwmarrgswjxmb\nqjhvhtzxzqqjkmpb\nxxyxx\n";
+
+    fn run(rules: u8, threads: usize, lines_per_thread: usize) -> (Counts, String, String) {
+        let options = StreamOptions { rules, alphabet: Alphabet::Chars, threads, lines_per_thread };
+        let (mut nice, mut naughty) = (Vec::new(), Vec::new());
+        let counts = classify_stream(SAMPLES.as_bytes(), &options, &mut nice, &mut naughty).unwrap();
+        (counts, String::from_utf8(nice).unwrap(), String::from_utf8(naughty).unwrap())
+    }
+
+    #[test]
+    fn test_split_preserves_order()
+    {
+        let (counts, nice, naughty) = run(RULESET1, 3, 1);
+        assert_eq!(counts, Counts { nice: 2, naughty: 5 });
+        assert_eq!(nice, "ugknbfddgicrmopn\naaa\n");
+        assert_eq!(naughty, "jchzalrnumimnmhp\nhaegwjzuvuyypxyu\ndvszwmarrgswjxmb\nqjhvhtzxzqqjkmpb\nxxyxx\n");
+    }
+
+    #[test]
+    fn test_batching_does_not_change_result()
+    {
+        let expected = run(RULESET2, 1, 1000);
+        for (threads, lines_per_thread) in [(1, 1), (2, 2), (4, 3), (8, 1)] {
+            assert_eq!(run(RULESET2, threads, lines_per_thread), expected);
+        }
+    }
+}
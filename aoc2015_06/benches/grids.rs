@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc2015_06::bitgrid::GridBits;
+use aoc2015_06::compress::solve_compressed;
+use aoc2015_06::instruction::Instruction;
+use aoc2015_06::parallel::execute;
+use aoc2015_06::sparse::SparseOnOff;
+use aoc2015_06::*;
+
+#[path = "../src/random.rs"]
+mod random;
+
+use random::{random_ops, Rng};
+
+fn on_off(c: &mut Criterion) {
+    let ops = random_ops(&mut Rng::new(2015), 300, DEFAULT_SIZE, DEFAULT_SIZE);
+    let mut group = c.benchmark_group("300_ops");
+    group.sample_size(10);
+    group.bench_function("grid_on_off", |b| b.iter(|| {
+        let mut grid = GridOnOff::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        ops.iter().for_each(|op| grid.apply_op(op));
+        grid.count_lights_on()
+    }));
+    group.bench_function("grid_bits", |b| b.iter(|| {
+        let mut grid = GridBits::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        ops.iter().for_each(|op| grid.apply_op(op));
+        grid.count_lights_on()
+    }));
+    group.bench_function("compressed_on_off", |b| b.iter(|| {
+        solve_compressed::<GridBits>(&ops, DEFAULT_SIZE, DEFAULT_SIZE)
+    }));
+    group.bench_function("sparse_on_off", |b| b.iter(|| {
+        let mut grid = SparseOnOff::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        ops.iter().for_each(|op| grid.apply_op(op));
+        grid.total()
+    }));
+    let program: Vec<Instruction> = ops.iter().cloned().map(Instruction::from).collect();
+    group.bench_function("grid_on_off_4_threads", |b| b.iter(|| {
+        execute::<GridOnOff>(&program, DEFAULT_SIZE, DEFAULT_SIZE, 4).count_lights_on()
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, on_off);
+criterion_main!(benches);
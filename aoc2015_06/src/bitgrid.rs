@@ -0,0 +1,163 @@
+// On/off lights packed 64 to a word. Each row is a run of words with bit x%64
+// of word x/64 holding light x, so an instruction turns on, turns off or
+// toggles a row span with one masked operation per word, and counting is a
+// popcount per word.
+
+use crate::{GridLike, GridOp, OpCode};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Clone)]
+pub struct GridBits {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+}
+
+// Bits first..=last of a word, both below 64.
+fn mask(first: usize, last: usize) -> u64 {
+    (u64::MAX << first) & (u64::MAX >> (WORD_BITS - 1 - last))
+}
+
+impl GridBits
+{
+    fn position(&self, x: usize, y: usize) -> (usize, u64) {
+        (y * self.words_per_row + x / WORD_BITS, 1 << (x % WORD_BITS))
+    }
+
+    fn apply_span(&mut self, op: &GridOp, f: fn(&mut u64, u64)) {
+        if op.x0 > op.x1 {
+            return;
+        }
+        let (first_word, last_word) = (op.x0 / WORD_BITS, op.x1 / WORD_BITS);
+        for y in op.y0..=op.y1 {
+            let row = &mut self.bits[y * self.words_per_row..(y + 1) * self.words_per_row];
+            for (w, word) in row.iter_mut().enumerate().take(last_word + 1).skip(first_word) {
+                let first = if w == first_word { op.x0 % WORD_BITS } else { 0 };
+                let last = if w == last_word { op.x1 % WORD_BITS } else { WORD_BITS - 1 };
+                f(word, mask(first, last));
+            }
+        }
+    }
+
+    pub fn count_lights_on(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+impl GridLike for GridBits
+{
+    fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        GridBits { width, height, words_per_row, bits: vec![0; words_per_row * height] }
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn turn_on(&mut self, x: usize, y: usize) {
+        let (word, bit) = self.position(x, y);
+        self.bits[word] |= bit;
+    }
+
+    fn turn_off(&mut self, x: usize, y: usize) {
+        let (word, bit) = self.position(x, y);
+        self.bits[word] &= !bit;
+    }
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        let (word, bit) = self.position(x, y);
+        self.bits[word] ^= bit;
+    }
+
+    fn light(&self, x: usize, y: usize) -> usize {
+        let (word, bit) = self.position(x, y);
+        (self.bits[word] & bit != 0) as usize
+    }
+
+    fn set_light(&mut self, x: usize, y: usize, value: usize) {
+        if value > 0 {
+            self.turn_on(x, y);
+        } else {
+            self.turn_off(x, y);
+        }
+    }
+
+    fn apply_op(&mut self, op: &GridOp)
+    {
+        match op.op_code {
+            OpCode::On => self.apply_span(op, |word, mask| *word |= mask),
+            OpCode::Off => self.apply_span(op, |word, mask| *word &= !mask),
+            OpCode::Toggle => self.apply_span(op, |word, mask| *word ^= mask),
+            OpCode::Set(0) => self.apply_span(op, |word, mask| *word &= !mask),
+            OpCode::Set(_) => self.apply_span(op, |word, mask| *word |= mask),
+            OpCode::Dim(0) => (),
+            OpCode::Dim(_) => self.apply_span(op, |word, mask| *word &= !mask),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::GridOnOff;
+
+    #[test]
+    fn test_mask()
+    {
+        assert_eq!(mask(0, 63), u64::MAX);
+        assert_eq!(mask(0, 0), 1);
+        assert_eq!(mask(63, 63), 1 << 63);
+        assert_eq!(mask(4, 7), 0xF0);
+    }
+
+    #[test]
+    fn test_matches_grid_on_off()
+    {
+        // 130 columns spill into a partial third word per row.
+        let (width, height) = (130, 7);
+        let ops = [
+            GridOp::new("turn on", "0", "0", "129", "6"),
+            GridOp::new("toggle", "3", "1", "64", "5"),
+            GridOp::new("turn off", "63", "0", "127", "2"),
+            GridOp::new("toggle", "64", "6", "64", "6"),
+            GridOp::new("turn on", "10", "3", "12", "3"),
+            GridOp { op_code: OpCode::Dim(1), x0: 0, y0: 0, x1: 70, y1: 0 },
+            GridOp { op_code: OpCode::Set(4), x0: 60, y0: 4, x1: 129, y1: 5 },
+            GridOp { op_code: OpCode::Set(0), x0: 100, y0: 5, x1: 110, y1: 6 },
+            GridOp { op_code: OpCode::Dim(0), x0: 0, y0: 0, x1: 129, y1: 6 },
+        ];
+        let mut packed = GridBits::new(width, height);
+        let mut reference = GridOnOff::new(width, height);
+        for op in &ops {
+            packed.apply_op(op);
+            reference.apply_op(op);
+            assert_eq!(packed.count_lights_on(), reference.count_lights_on());
+        }
+        for x in 0..width {
+            for y in 0..height {
+                assert_eq!(packed.light(x, y), reference.light(x, y), "{},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_lights()
+    {
+        let mut grid = GridBits::new(65, 2);
+        grid.turn_on(64, 1);
+        grid.toggle(0, 0);
+        grid.toggle(0, 0);
+        assert_eq!(grid.count_lights_on(), 1);
+        assert_eq!(grid.light(64, 1), 1);
+        grid.turn_off(64, 1);
+        assert_eq!(grid.count_lights_on(), 0);
+    }
+}
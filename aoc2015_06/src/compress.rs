@@ -0,0 +1,102 @@
+// Solves an instruction list without materialising the full grid. The
+// rectangle edges cut each axis into intervals whose lights always change
+// together, so a grid with one light per pair of intervals, weighted by the
+// area of the pair, gives the same total for any GridLike semantics.
+
+use crate::{GridLike, GridOp};
+
+struct Axis {
+    // Sorted start of every interval, followed by the axis length.
+    starts: Vec<usize>,
+}
+
+impl Axis
+{
+    fn new(len: usize, spans: impl Iterator<Item = (usize, usize)>) -> Axis {
+        let mut starts = vec![0, len];
+        for (first, last) in spans {
+            starts.push(first);
+            starts.push(last + 1);
+        }
+        starts.sort_unstable();
+        starts.dedup();
+        Axis { starts }
+    }
+
+    fn intervals(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    fn interval_len(&self, i: usize) -> usize {
+        self.starts[i + 1] - self.starts[i]
+    }
+
+    // Every span edge is an interval start, so the search always hits.
+    fn index(&self, coordinate: usize) -> usize {
+        self.starts.binary_search(&coordinate).expect("Coordinate is not an interval edge")
+    }
+
+    fn compress(&self, first: usize, last: usize) -> (usize, usize) {
+        (self.index(first), self.index(last + 1) - 1)
+    }
+}
+
+// Sum of light values, i.e. lights on for GridOnOff and total brightness for
+// GridBrightness. The instructions must already fit the grid.
+pub fn solve_compressed<G: GridLike>(ops: &[GridOp], width: usize, height: usize) -> usize
+{
+    let xs = Axis::new(width, ops.iter().map(|op| (op.x0, op.x1)));
+    let ys = Axis::new(height, ops.iter().map(|op| (op.y0, op.y1)));
+
+    let mut grid = G::new(xs.intervals(), ys.intervals());
+    for op in ops {
+        let (x0, x1) = xs.compress(op.x0, op.x1);
+        let (y0, y1) = ys.compress(op.y0, op.y1);
+        grid.apply_op(&GridOp { x0, y0, x1, y1, ..op.clone() });
+    }
+
+    let mut total = 0;
+    for x in 0..xs.intervals() {
+        for y in 0..ys.intervals() {
+            total += grid.light(x, y) * xs.interval_len(x) * ys.interval_len(y);
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::random::{random_ops, Rng};
+    use crate::{GridBrightness, GridOnOff};
+
+    #[test]
+    fn test_matches_full_grid()
+    {
+        let (width, height) = (97, 61);
+        let ops = random_ops(&mut Rng::new(42), 200, width, height);
+        let mut on_off = GridOnOff::new(width, height);
+        let mut brightness = GridBrightness::new(width, height);
+        for op in &ops {
+            on_off.apply_op(op);
+            brightness.apply_op(op);
+        }
+        assert_eq!(solve_compressed::<GridOnOff>(&ops, width, height), on_off.count_lights_on());
+        assert_eq!(solve_compressed::<GridBrightness>(&ops, width, height), brightness.count_brightness());
+    }
+
+    #[test]
+    fn test_huge_grid()
+    {
+        let size = 1_000_000_000;
+        let ops = [
+            GridOp::new("turn on", "0", "0", "999999999", "999999999"),
+            GridOp::new("toggle", "0", "0", "499999999", "999999999"),
+            GridOp::new("turn off", "0", "0", "0", "0"),
+        ];
+        assert_eq!(solve_compressed::<GridOnOff>(&ops, size, size), size * size / 2);
+        assert_eq!(solve_compressed::<GridBrightness>(&ops, size, size), size * size / 2 * 4 - 1);
+        assert_eq!(solve_compressed::<GridOnOff>(&[], 3, 2), 0);
+    }
+}
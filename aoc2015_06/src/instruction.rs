@@ -0,0 +1,338 @@
+// Instructions beyond rectangles. Every shape lowers to GridOps over row or
+// rectangle spans clipped to the grid, so each GridLike backend runs them
+// through its own apply_op; only copy needs to read lights back.
+
+use std::fmt;
+
+use crate::{Bounds, GridLike, GridOp, OpCode};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect { x0: usize, y0: usize, x1: usize, y1: usize },
+    // Every light of the grid except those in the rectangle.
+    Outside { x0: usize, y0: usize, x1: usize, y1: usize },
+    // Lights within `radius` of the centre, edges included.
+    Circle { x: usize, y: usize, radius: usize },
+    // The lights a Bresenham line between the two ends passes through.
+    Line { x0: usize, y0: usize, x1: usize, y1: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Paint(OpCode, Shape),
+    // Copies the rectangle so its top left corner lands on (x, y).
+    Copy { x0: usize, y0: usize, x1: usize, y1: usize, x: usize, y: usize },
+}
+
+impl From<GridOp> for Instruction {
+    fn from(op: GridOp) -> Instruction {
+        Instruction::Paint(op.op_code, Shape::Rect { x0: op.x0, y0: op.y0, x1: op.x1, y1: op.y1 })
+    }
+}
+
+fn op(op_code: OpCode, x0: usize, y0: usize, x1: usize, y1: usize) -> GridOp {
+    GridOp { op_code, x0, y0, x1, y1 }
+}
+
+// Offset along the shorter axis after `step` Bresenham steps on a line that
+// moves `long` along one axis and `short` along the other: the error term
+// keeps each light nearest the true line, with halves rounded away from the
+// start. The product fits in u128 for any usize line.
+fn along_line(step: u128, short: u128, long: u128) -> u128 {
+    if long == 0 {
+        return 0;
+    }
+    let (whole, rest) = (step * short / long, step * short % long);
+    whole + (2 * rest >= long) as u128
+}
+
+impl Shape
+{
+    // The shape as rectangles within a width x height grid. Coordinates go
+    // through i128 so any usize fits with room for signs and offsets.
+    fn grid_ops(&self, op_code: OpCode, width: usize, height: usize) -> Vec<GridOp> {
+        let (max_x, max_y) = (width as i128 - 1, height as i128 - 1);
+        let mut ops = Vec::new();
+        let mut push = |x0: i128, y0: i128, x1: i128, y1: i128| {
+            let (x0, y0, x1, y1) = (x0.max(0), y0.max(0), x1.min(max_x), y1.min(max_y));
+            if x0 <= x1 && y0 <= y1 {
+                ops.push(op(op_code, x0 as usize, y0 as usize, x1 as usize, y1 as usize));
+            }
+        };
+        match *self {
+            Shape::Rect { x0, y0, x1, y1 } => push(x0 as i128, y0 as i128, x1 as i128, y1 as i128),
+            Shape::Outside { x0, y0, x1, y1 } => {
+                let (x0, y0, x1, y1) = (x0 as i128, y0 as i128, x1 as i128, y1 as i128);
+                push(0, 0, max_x, y0 - 1);
+                push(0, y1 + 1, max_x, max_y);
+                push(0, y0, x0 - 1, y1);
+                push(x1 + 1, y0, max_x, y1);
+            }
+            Shape::Circle { x, y, radius } => {
+                let (x, y, radius) = (x as i128, y as i128, radius as i128);
+                // Rows off the grid are skipped rather than clipped to nothing.
+                for dy in (-radius).max(-y)..=radius.min(max_y - y) {
+                    // Squares of up to usize::MAX only fit unsigned.
+                    let half = (radius.unsigned_abs().pow(2) - dy.unsigned_abs().pow(2)).isqrt() as i128;
+                    push(x - half, y + dy, x + half, y + dy);
+                }
+            }
+            Shape::Line { x0, y0, x1, y1 } => {
+                let (x0, y0, x1, y1) = (x0 as i128, y0 as i128, x1 as i128, y1 as i128);
+                let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+                let (dx, dy) = ((x1 - x0).unsigned_abs(), (y1 - y0).unsigned_abs());
+                // Bresenham moves along the longer axis on every step, so only
+                // the steps that land on the grid along it are walked; the
+                // light for each step comes from along_line.
+                let (long, short) = (dx.max(dy), dx.min(dy));
+                let (start, step, max) = if dx >= dy { (x0, step_x, max_x) } else { (y0, step_y, max_y) };
+                let (first, last) = if step >= 0 { (-start, max - start) } else { (start - max, start) };
+                let mut cells = Vec::new();
+                for t in first.max(0)..=last.min(long as i128) {
+                    let across = along_line(t as u128, short, long) as i128;
+                    let (x, y) = if dx >= dy { (x0 + step_x * t, y0 + step_y * across) } else { (x0 + step_x * across, y0 + step_y * t) };
+                    if (0..=max_y).contains(&y) && (0..=max_x).contains(&x) {
+                        cells.push((x, y));
+                    }
+                }
+                // Consecutive lights on one row become a single span.
+                for row in cells.chunk_by(|a, b| a.1 == b.1) {
+                    let (first, last) = (row[0].0, row[row.len() - 1].0);
+                    push(first.min(last), row[0].1, first.max(last), row[0].1);
+                }
+            }
+        }
+        ops
+    }
+
+    // Points that must lie on the grid for the shape to be in bounds. A
+    // circle only needs its centre, the rest of it is clipped.
+    fn anchors(&self) -> Vec<(usize, usize)> {
+        match *self {
+            Shape::Rect { x0, y0, x1, y1 } | Shape::Outside { x0, y0, x1, y1 } | Shape::Line { x0, y0, x1, y1 } =>
+                vec![(x0, y0), (x1, y1)],
+            Shape::Circle { x, y, .. } => vec![(x, y)],
+        }
+    }
+}
+
+impl Instruction
+{
+    // Rectangles equivalent to the instruction, or None for copy, whose effect
+    // depends on the lights.
+    pub fn grid_ops(&self, width: usize, height: usize) -> Option<Vec<GridOp>> {
+        match self {
+            Instruction::Paint(op_code, shape) => Some(shape.grid_ops(*op_code, width, height)),
+            Instruction::Copy { .. } => None,
+        }
+    }
+
+    // Rectangles, clipped to the grid and not overlapping, that hold every
+    // light the instruction can change.
+    pub fn footprint(&self, width: usize, height: usize) -> Vec<(usize, usize, usize, usize)> {
+        match *self {
+            Instruction::Paint(op_code, ref shape) =>
+                shape.grid_ops(op_code, width, height).iter().map(|op| (op.x0, op.y0, op.x1, op.y1)).collect(),
+            Instruction::Copy { x0, y0, x1, y1, x, y } => {
+                let target = Shape::Rect { x0: x, y0: y, x1: x + x1.saturating_sub(x0), y1: y + y1.saturating_sub(y0) };
+                target.grid_ops(OpCode::On, width, height).iter().map(|op| (op.x0, op.y0, op.x1, op.y1)).collect()
+            }
+        }
+    }
+
+    fn anchors(&self) -> Vec<(usize, usize)> {
+        match *self {
+            Instruction::Paint(_, ref shape) => shape.anchors(),
+            Instruction::Copy { x0, y0, x1, y1, x, y } =>
+                vec![(x0, y0), (x1, y1), (x, y), (x + x1.saturating_sub(x0), y + y1.saturating_sub(y0))],
+        }
+    }
+
+    // Shapes are always clipped to the grid when applied, so only Strict
+    // bounds can reject an instruction.
+    pub fn check(&self, width: usize, height: usize, bounds: Bounds) -> Result<(), String> {
+        if bounds == Bounds::Strict && self.anchors().iter().any(|&(x, y)| x >= width || y >= height) {
+            return Err(format!("'{}' reaches outside the {}x{} grid", self, width, height));
+        }
+        Ok(())
+    }
+
+    pub fn apply<G: GridLike>(&self, grid: &mut G) {
+        match *self {
+            Instruction::Paint(..) => {
+                for op in self.grid_ops(grid.width(), grid.height()).unwrap_or_default() {
+                    grid.apply_op(&op);
+                }
+            }
+            Instruction::Copy { x0, y0, x1, y1, x, y } => grid.copy_rect(x0, y0, x1, y1, x, y),
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Shape::Rect { x0, y0, x1, y1 } => write!(f, "{},{} through {},{}", x0, y0, x1, y1),
+            Shape::Outside { x0, y0, x1, y1 } => write!(f, "outside {},{} through {},{}", x0, y0, x1, y1),
+            Shape::Circle { x, y, radius } => write!(f, "circle {},{} radius {}", x, y, radius),
+            Shape::Line { x0, y0, x1, y1 } => write!(f, "line {},{} to {},{}", x0, y0, x1, y1),
+        }
+    }
+}
+
+// Written in the same language the parser reads.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Paint(OpCode::On, shape) => write!(f, "turn on {}", shape),
+            Instruction::Paint(OpCode::Off, shape) => write!(f, "turn off {}", shape),
+            Instruction::Paint(OpCode::Toggle, shape) => write!(f, "toggle {}", shape),
+            Instruction::Paint(OpCode::Set(value), shape) => write!(f, "set {} to {}", shape, value),
+            Instruction::Paint(OpCode::Dim(amount), shape) => write!(f, "dim {} by {}", shape, amount),
+            Instruction::Copy { x0, y0, x1, y1, x, y } => write!(f, "copy {},{} through {},{} to {},{}", x0, y0, x1, y1, x, y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bitgrid::GridBits;
+    use crate::{GridBrightness, GridOnOff};
+
+    fn lit<G: GridLike>(grid: &G) -> Vec<(usize, usize)> {
+        let mut lit = Vec::new();
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                if grid.light(x, y) > 0 {
+                    lit.push((x, y));
+                }
+            }
+        }
+        lit
+    }
+
+    fn paint(shape: Shape, width: usize, height: usize) -> GridOnOff {
+        let mut grid = GridOnOff::new(width, height);
+        Instruction::Paint(OpCode::On, shape).apply(&mut grid);
+        grid
+    }
+
+    #[test]
+    fn test_outside()
+    {
+        let grid = paint(Shape::Outside { x0: 1, y0: 1, x1: 3, y1: 2 }, 5, 4);
+        assert_eq!(grid.count_lights_on(), 20 - 6);
+        assert!(lit(&grid).iter().all(|&(x, y)| !(1..=3).contains(&x) || !(1..=2).contains(&y)));
+        assert_eq!(paint(Shape::Outside { x0: 9, y0: 9, x1: 20, y1: 20 }, 5, 4).count_lights_on(), 20);
+    }
+
+    #[test]
+    fn test_circle()
+    {
+        let grid = paint(Shape::Circle { x: 2, y: 2, radius: 2 }, 5, 5);
+        assert_eq!(grid.count_lights_on(), 13);
+        assert_eq!(lit(&paint(Shape::Circle { x: 0, y: 0, radius: 1 }, 5, 5)), [(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_huge_circle()
+    {
+        assert_eq!(paint(Shape::Circle { x: 5, y: 5, radius: 4_000_000_000 }, 10, 10).count_lights_on(), 100);
+        assert_eq!(paint(Shape::Circle { x: 0, y: 9, radius: usize::MAX }, 10, 10).count_lights_on(), 100);
+        // Centred far off the grid, the edge of the circle passes through it.
+        let edge = paint(Shape::Circle { x: usize::MAX, y: 0, radius: usize::MAX - 5 }, 10, 10);
+        assert_eq!(lit(&edge).iter().filter(|&&(_, y)| y == 0).count(), 5);
+    }
+
+    #[test]
+    fn test_line()
+    {
+        assert_eq!(lit(&paint(Shape::Line { x0: 0, y0: 0, x1: 4, y1: 2 }, 5, 3)), [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+        // Ties may round the other way when drawn backwards.
+        let backwards = lit(&paint(Shape::Line { x0: 4, y0: 2, x1: 0, y1: 0 }, 5, 3));
+        assert_eq!(backwards.len(), 5);
+        assert!(backwards.contains(&(0, 0)) && backwards.contains(&(2, 1)) && backwards.contains(&(4, 2)));
+        assert_eq!(lit(&paint(Shape::Line { x0: 1, y0: 3, x1: 1, y1: 0 }, 3, 4)), [(1, 0), (1, 1), (1, 2), (1, 3)]);
+        assert_eq!(lit(&paint(Shape::Line { x0: 2, y0: 1, x1: 2, y1: 1 }, 3, 3)), [(2, 1)]);
+        let spans = Shape::Line { x0: 0, y0: 0, x1: 9, y1: 1 }.grid_ops(OpCode::On, 10, 2);
+        assert_eq!(spans, [op(OpCode::On, 0, 0, 4, 0), op(OpCode::On, 5, 1, 9, 1)]);
+    }
+
+    #[test]
+    fn test_long_line()
+    {
+        // Only the steps on the grid are walked.
+        let spans = Shape::Line { x0: 0, y0: 0, x1: 100_000_000_000, y1: 1 }.grid_ops(OpCode::On, 10, 2);
+        assert_eq!(spans, [op(OpCode::On, 0, 0, 9, 0)]);
+        let spans = Shape::Line { x0: usize::MAX, y0: 3, x1: 0, y1: 0 }.grid_ops(OpCode::On, 4, 4);
+        assert_eq!(spans, [op(OpCode::On, 0, 0, 3, 0)]);
+        let steep = lit(&paint(Shape::Line { x0: 2, y0: 1_000_000_000_003, x1: 0, y1: 0 }, 3, 4));
+        assert_eq!(steep, [(0, 0), (0, 1), (0, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn test_set_and_dim()
+    {
+        let mut grid = GridBrightness::new(3, 1);
+        Instruction::Paint(OpCode::Set(7), Shape::Rect { x0: 0, y0: 0, x1: 2, y1: 0 }).apply(&mut grid);
+        Instruction::Paint(OpCode::Dim(5), Shape::Rect { x0: 1, y0: 0, x1: 2, y1: 0 }).apply(&mut grid);
+        Instruction::Paint(OpCode::Dim(5), Shape::Rect { x0: 2, y0: 0, x1: 2, y1: 0 }).apply(&mut grid);
+        assert_eq!((grid.light(0, 0), grid.light(1, 0), grid.light(2, 0)), (7, 2, 0));
+
+        let mut grid = GridOnOff::new(3, 1);
+        Instruction::Paint(OpCode::Set(7), Shape::Rect { x0: 0, y0: 0, x1: 2, y1: 0 }).apply(&mut grid);
+        Instruction::Paint(OpCode::Dim(1), Shape::Rect { x0: 1, y0: 0, x1: 1, y1: 0 }).apply(&mut grid);
+        assert_eq!(lit(&grid), [(0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn test_copy_overlapping()
+    {
+        let (mut on_off, mut brightness) = (GridOnOff::new(6, 2), GridBrightness::new(6, 2));
+        let program = [
+            Instruction::Paint(OpCode::Set(3), Shape::Rect { x0: 0, y0: 0, x1: 0, y1: 0 }),
+            Instruction::Paint(OpCode::Set(5), Shape::Rect { x0: 1, y0: 0, x1: 1, y1: 0 }),
+            Instruction::Copy { x0: 0, y0: 0, x1: 2, y1: 0, x: 1, y: 0 },
+            Instruction::Copy { x0: 0, y0: 0, x1: 5, y1: 0, x: 4, y: 1 },
+        ];
+        for instruction in &program {
+            instruction.apply(&mut on_off);
+            instruction.apply(&mut brightness);
+        }
+        let row = |y| (0..6).map(|x| brightness.light(x, y)).collect::<Vec<_>>();
+        assert_eq!(row(0), [3, 3, 5, 0, 0, 0]);
+        assert_eq!(row(1), [0, 0, 0, 0, 3, 3]);
+        assert_eq!(lit(&on_off), [(0, 0), (1, 0), (2, 0), (4, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn test_backends_agree()
+    {
+        let program = [
+            Instruction::Paint(OpCode::On, Shape::Circle { x: 40, y: 20, radius: 30 }),
+            Instruction::Paint(OpCode::Toggle, Shape::Outside { x0: 10, y0: 5, x1: 90, y1: 30 }),
+            Instruction::Paint(OpCode::Off, Shape::Line { x0: 0, y0: 39, x1: 99, y1: 0 }),
+            Instruction::Copy { x0: 0, y0: 0, x1: 49, y1: 19, x: 60, y: 25 },
+            Instruction::Paint(OpCode::Dim(1), Shape::Rect { x0: 70, y0: 0, x1: 80, y1: 39 }),
+        ];
+        let mut on_off = GridOnOff::new(100, 40);
+        let mut packed = GridBits::new(100, 40);
+        for instruction in &program {
+            instruction.apply(&mut on_off);
+            instruction.apply(&mut packed);
+        }
+        assert_eq!(lit(&on_off), lit(&packed));
+        assert!(on_off.count_lights_on() > 0);
+    }
+
+    #[test]
+    fn test_check()
+    {
+        let copy = Instruction::Copy { x0: 0, y0: 0, x1: 2, y1: 2, x: 8, y: 0 };
+        assert_eq!(copy.check(10, 10, Bounds::Strict), Err("'copy 0,0 through 2,2 to 8,0' reaches outside the 10x10 grid".to_string()));
+        assert_eq!(copy.check(10, 10, Bounds::Clip), Ok(()));
+        assert_eq!(Instruction::Paint(OpCode::On, Shape::Circle { x: 0, y: 0, radius: 50 }).check(10, 10, Bounds::Strict), Ok(()));
+    }
+}
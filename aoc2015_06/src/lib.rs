@@ -0,0 +1,308 @@
+pub mod bitgrid;
+pub mod compress;
+pub mod instruction;
+pub mod parallel;
+pub mod parse;
+pub mod provenance;
+pub mod query;
+pub mod render;
+pub mod simulate;
+pub mod sparse;
+#[cfg(test)]
+mod random;
+#[cfg(test)]
+mod testing;
+
+pub const DEFAULT_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    On,
+    Off,
+    Toggle,
+    // Brightness set to the value; on/off lights are on for any value above 0.
+    Set(usize),
+    // Brightness lowered by the amount, not below 0; on/off lights go off.
+    Dim(usize),
+}
+
+// What to do with an instruction that reaches outside the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bounds {
+    Strict,
+    Clip,
+}
+
+pub trait GridLike
+{
+    fn new(width: usize, height: usize) -> Self;
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn turn_on(&mut self, x: usize, y: usize);
+    fn turn_off(&mut self, x: usize, y: usize);
+    fn toggle(&mut self, x: usize, y: usize);
+    // 0 or 1 for a light that is off or on, its brightness otherwise.
+    fn light(&self, x: usize, y: usize) -> usize;
+    fn set_light(&mut self, x: usize, y: usize, value: usize);
+    fn apply_range(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, f: &dyn Fn(&mut Self, usize, usize))
+    {
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                f(self, x, y);
+            }
+        }
+    }
+    fn apply_op(&mut self, op: &GridOp)
+    {
+        match op.op_code {
+            OpCode::On => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::turn_on),
+            OpCode::Off => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::turn_off),
+            OpCode::Toggle => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::toggle),
+            OpCode::Set(value) => self.apply_range(op.x0, op.y0, op.x1, op.y1, &|grid: &mut Self, x, y| grid.set_light(x, y, value)),
+            OpCode::Dim(amount) => self.apply_range(op.x0, op.y0, op.x1, op.y1, &|grid: &mut Self, x, y| {
+                grid.set_light(x, y, grid.light(x, y).saturating_sub(amount))
+            }),
+        }
+    }
+    // Copies x0,y0 through x1,y1 to x,y, reading the whole source first so
+    // overlapping regions copy correctly. Parts off the grid are dropped.
+    fn copy_rect(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, x: usize, y: usize)
+    {
+        let (x1, y1) = (x1.min(self.width().saturating_sub(1)), y1.min(self.height().saturating_sub(1)));
+        let mut lights = Vec::new();
+        for sx in x0..=x1 {
+            for sy in y0..=y1 {
+                lights.push((x + sx - x0, y + sy - y0, self.light(sx, sy)));
+            }
+        }
+        for (tx, ty, value) in lights {
+            if tx < self.width() && ty < self.height() {
+                self.set_light(tx, ty, value);
+            }
+        }
+    }
+    fn try_apply_op(&mut self, op: &GridOp, bounds: Bounds) -> Result<(), String>
+    {
+        if let Some(op) = op.fit(self.width(), self.height(), bounds)? {
+            self.apply_op(&op);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct GridOnOff {
+    grid: Vec<Vec<bool>>,
+}
+
+impl GridLike for GridOnOff
+{
+    fn new(width: usize, height: usize) -> Self {
+        let grid = vec![vec![false; height]; width];
+
+        GridOnOff { grid }
+    }
+
+    fn width(&self) -> usize {
+        self.grid.len()
+    }
+
+    fn height(&self) -> usize {
+        self.grid.first().map_or(0, Vec::len)
+    }
+
+    fn turn_on(&mut self, x: usize, y: usize) {
+        self.grid[x][y] = true;
+    }
+
+    fn turn_off(&mut self, x: usize, y: usize) {
+        self.grid[x][y] = false;
+    }
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        self.grid[x][y] = !self.grid[x][y];
+    }
+
+    fn light(&self, x: usize, y: usize) -> usize {
+        self.grid[x][y] as usize
+    }
+
+    fn set_light(&mut self, x: usize, y: usize, value: usize) {
+        self.grid[x][y] = value > 0;
+    }
+}
+
+impl GridOnOff
+{
+    pub fn count_lights_on(&self) -> usize {
+        self.grid.iter().flatten().filter(|&&x| x).count()
+    }
+}
+
+#[derive(Clone)]
+pub struct GridBrightness {
+    grid: Vec<Vec<usize>>,
+}
+
+impl GridLike for GridBrightness
+{
+    fn new(width: usize, height: usize) -> Self {
+        let grid = vec![vec![0; height]; width];
+
+        GridBrightness { grid }
+    }
+
+    fn width(&self) -> usize {
+        self.grid.len()
+    }
+
+    fn height(&self) -> usize {
+        self.grid.first().map_or(0, Vec::len)
+    }
+
+    fn turn_on(&mut self, x: usize, y: usize) {
+        self.grid[x][y] += 1;
+    }
+
+    fn turn_off(&mut self, x: usize, y: usize) {
+        if self.grid[x][y] > 0 {
+            self.grid[x][y] -= 1;
+        }
+    }
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        self.grid[x][y] += 2;
+    }
+
+    fn light(&self, x: usize, y: usize) -> usize {
+        self.grid[x][y]
+    }
+
+    fn set_light(&mut self, x: usize, y: usize, value: usize) {
+        self.grid[x][y] = value;
+    }
+}
+
+impl GridBrightness
+{
+    pub fn count_brightness(&self) -> usize {
+        self.grid.iter().flatten().sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridOp {
+    pub op_code: OpCode,
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl GridOp
+{
+    pub fn new(op_code_s: &str, x0_s: &str, y0_s: &str, x1_s: &str, y1_s: &str) -> GridOp {
+        let op_code = match op_code_s {
+            "turn on" => OpCode::On,
+            "turn off" => OpCode::Off,
+            "toggle" => OpCode::Toggle,
+            _ => panic!("Unknown op code: {}", op_code_s),
+        };
+        let x0 = x0_s.parse::<usize>().unwrap();
+        let y0 = y0_s.parse::<usize>().unwrap();
+        let x1 = x1_s.parse::<usize>().unwrap();
+        let y1 = y1_s.parse::<usize>().unwrap();
+        GridOp { op_code, x0, y0, x1, y1 }
+    }
+
+    // The instruction as it applies to a width x height grid: unchanged when it
+    // fits, an error or the clipped rectangle when it does not, and None when
+    // clipping leaves nothing.
+    pub fn fit(&self, width: usize, height: usize, bounds: Bounds) -> Result<Option<GridOp>, String> {
+        if self.x1 < width && self.y1 < height {
+            return Ok(Some(self.clone()));
+        }
+        match bounds {
+            Bounds::Strict => Err(format!("{},{} through {},{} is outside the {}x{} grid",
+                                          self.x0, self.y0, self.x1, self.y1, width, height)),
+            Bounds::Clip if self.x0 >= width || self.y0 >= height => Ok(None),
+            Bounds::Clip => Ok(Some(GridOp { x1: self.x1.min(width - 1), y1: self.y1.min(height - 1), ..self.clone() })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    #[test]
+    fn test_turn_on_all()
+    {
+        let mut grid = GridOnOff::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        grid.apply_op(&GridOp::new("turn on", "0", "0", "999", "999"));
+        assert_eq!(grid.count_lights_on(), DEFAULT_SIZE * DEFAULT_SIZE);
+    }
+
+    #[test]
+    fn test_toggle_1st_row()
+    {
+        let mut grid = GridOnOff::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        grid.apply_op(&GridOp::new("toggle", "0", "0", "999", "0"));
+        assert_eq!(grid.count_lights_on(), DEFAULT_SIZE);
+    }
+
+    #[test]
+    fn test_turn_on_middle_4()
+    {
+        let mut grid = GridOnOff::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        grid.apply_op(&GridOp::new("turn on", "499", "499", "500", "500"));
+        assert_eq!(grid.count_lights_on(), 4);
+    }
+
+    #[test]
+    fn test_turn_bright_1()
+    {
+        let mut grid = GridBrightness::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        grid.apply_op(&GridOp::new("turn on", "0", "0", "0", "0"));
+        assert_eq!(grid.count_brightness(),  1);
+    }
+
+    #[test]
+    fn test_turn_bright_all()
+    {
+        let mut grid = GridBrightness::new(DEFAULT_SIZE, DEFAULT_SIZE);
+        grid.apply_op(&GridOp::new("turn on", "0", "0", "999", "999"));
+        assert_eq!(grid.count_brightness(), DEFAULT_SIZE * DEFAULT_SIZE);
+    }
+
+    #[test]
+    fn test_non_square_grid()
+    {
+        let mut grid = GridOnOff::new(8, 3);
+        assert_eq!((grid.width(), grid.height()), (8, 3));
+        grid.try_apply_op(&GridOp::new("turn on", "0", "0", "7", "2"), Bounds::Strict).unwrap();
+        assert_eq!(grid.count_lights_on(), 24);
+        assert!(grid.try_apply_op(&GridOp::new("toggle", "0", "0", "2", "7"), Bounds::Strict).is_err());
+        assert_eq!(grid.count_lights_on(), 24);
+    }
+
+    #[test]
+    fn test_clip_to_grid()
+    {
+        let mut grid = GridBrightness::new(4, 6);
+        grid.try_apply_op(&GridOp::new("toggle", "2", "4", "999", "999"), Bounds::Clip).unwrap();
+        assert_eq!(grid.count_brightness(), 2 * 2 * 2);
+        grid.try_apply_op(&GridOp::new("turn on", "4", "0", "9", "9"), Bounds::Clip).unwrap();
+        assert_eq!(grid.count_brightness(), 8);
+    }
+
+    #[test]
+    fn test_fit()
+    {
+        let op = GridOp::new("turn off", "1", "1", "5", "5");
+        assert_eq!(op.fit(6, 6, Bounds::Strict), Ok(Some(op.clone())));
+        assert_eq!(op.fit(3, 10, Bounds::Clip), Ok(Some(GridOp::new("turn off", "1", "1", "2", "5"))));
+        assert_eq!(op.fit(1, 10, Bounds::Clip), Ok(None));
+        assert_eq!(op.fit(3, 10, Bounds::Strict), Err("1,1 through 5,5 is outside the 3x10 grid".to_string()));
+    }
+}
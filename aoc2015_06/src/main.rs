@@ -1,197 +1,150 @@
 use std::env;
-use std::fs::*;
-use std::io::*;
-use regex::Regex;
-
-enum OpCode {
-    On,
-    Off,
-    Toggle,
-}
-
-trait GridLike
-{
-    const GRID_SIZE: usize = 1000;
-
-    fn new() -> Self;
-    fn turn_on(&mut self, x: usize, y: usize);
-    fn turn_off(&mut self, x: usize, y: usize);
-    fn toggle(&mut self, x: usize, y: usize);
-    fn apply_range(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, f: &dyn Fn(&mut Self, usize, usize))
-    {
-        for x in x0..=x1 {
-            for y in y0..=y1 {
-                f(self, x, y);
-            }
-        }
-    }
-    fn apply_op(&mut self, op: &GridOp)
-    {
-        match op.op_code {
-            OpCode::On => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::turn_on),
-            OpCode::Off => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::turn_off),
-            OpCode::Toggle => self.apply_range(op.x0, op.y0, op.x1, op.y1, &Self::toggle),
-        }
-    }    
-}
-
-struct GridOnOff {
-    grid: Vec<Vec<bool>>,
-}
-
-impl GridLike for GridOnOff
-{
-    fn new() -> Self {
-        let mut grid = vec![vec![false; <GridOnOff as GridLike>::GRID_SIZE]; <GridOnOff as GridLike>::GRID_SIZE];
-        
-        GridOnOff { grid }
-    }
-
-    fn turn_on(&mut self, x: usize, y: usize) {
-        self.grid[x][y] = true;
-    }
-
-    fn turn_off(&mut self, x: usize, y: usize) {
-        self.grid[x][y] = false;
-    }
-
-    fn toggle(&mut self, x: usize, y: usize) {
-        self.grid[x][y] = !self.grid[x][y];
-    }
-}
-
-impl GridOnOff
-{
-    fn count_lights_on(&self) -> usize {
-        self.grid.iter().flatten().filter(|&&x| x).count()
-    }
-}
-
-struct GridBrightness {
-    grid: Vec<Vec<usize>>,
-}
-
-impl GridLike for GridBrightness
-{
-    fn new() -> Self {
-        let mut grid = vec![vec![0; <GridBrightness as GridLike>::GRID_SIZE]; <GridBrightness as GridLike>::GRID_SIZE];
-        
-        GridBrightness { grid }
-    }
-
-    fn turn_on(&mut self, x: usize, y: usize) {
-        self.grid[x][y] += 1;
-    }
-
-    fn turn_off(&mut self, x: usize, y: usize) {
-        if (self.grid[x][y] > 0) {
-            self.grid[x][y] -= 1;
-        }
-    }
-
-    fn toggle(&mut self, x: usize, y: usize) {
-        self.grid[x][y] += 2;
-    }
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+
+use aoc2015_06::bitgrid::GridBits;
+use aoc2015_06::compress::solve_compressed;
+use aoc2015_06::parallel::execute;
+use aoc2015_06::parse::{parse_program, parse_rects};
+use aoc2015_06::provenance::provenance;
+use aoc2015_06::query::SummedArea;
+use aoc2015_06::simulate::Simulator;
+use aoc2015_06::sparse::{SparseBrightness, SparseOnOff};
+use aoc2015_06::render::{save_image, write_animation};
+use aoc2015_06::*;
+
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let idx = args.iter().position(|a| a == name)?;
+    Some(args.get(idx + 1).unwrap_or_else(|| panic!("{} expects a value", name)))
 }
 
-impl GridBrightness
-{
-    fn count_brightness(&self) -> usize {
-        self.grid.iter().flatten().sum()
+// Writes the grid to the .png or .pgm file given after `flag`, if any.
+fn save_image_arg<G: GridLike>(args: &[String], flag: &str, grid: &G) {
+    if let Some(path) = arg_value(args, flag) {
+        save_image(Path::new(path), grid).unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
     }
 }
 
-struct GridOp {
-    op_code: OpCode,
-    x0: usize,
-    y0: usize,
-    x1: usize,
-    y1: usize,
-}
-
-impl GridOp
-{
-    fn new(op_code_s: &str, x0_s: &str, y0_s: &str, x1_s: &str, y1_s: &str) -> GridOp {
-        let op_code = match op_code_s {
-            "turn on" => OpCode::On,
-            "turn off" => OpCode::Off,
-            "toggle" => OpCode::Toggle,
-            _ => panic!("Unknown op code: {}", op_code_s),
-        };
-        let x0 = x0_s.parse::<usize>().unwrap();
-        let y0 = y0_s.parse::<usize>().unwrap();
-        let x1 = x1_s.parse::<usize>().unwrap();
-        let y1 = y1_s.parse::<usize>().unwrap();
-        GridOp { op_code, x0, y0, x1, y1 }
-    }
-}
- 
-
 fn main() {
     let args: Vec<String> = env::args().collect();
     assert!((args.len() > 1), "Missing input file argument");
 
+    let width = arg_value(&args, "--width").map_or(DEFAULT_SIZE, |n| n.parse().expect("--width expects a number"));
+    let height = arg_value(&args, "--height").map_or(DEFAULT_SIZE, |n| n.parse().expect("--height expects a number"));
+    let bounds = if args.iter().any(|a| a == "--clip") { Bounds::Clip } else { Bounds::Strict };
+    let threads = arg_value(&args, "--threads").map_or_else(
+        || thread::available_parallelism().map_or(1, |n| n.get()),
+        |n| n.parse().expect("--threads expects a number"));
+
     let file = File::open(&args[1]).unwrap(); 
     let mut data: String = String::new();
     BufReader::new(file).read_to_string(&mut data).expect("Failed to read file");
     
-    let re = Regex::new(r"(turn\soff|turn\son|toggle)\s(\d+),(\d+)\D+(\d+),(\d+)").unwrap();
-    let ops: Vec<GridOp> = re.captures_iter(&data)
-        .map(|cap|GridOp::new(&cap[1], &cap[2], &cap[3], &cap[4], &cap[5]))
-        .collect();
-  
-    let mut grid = GridOnOff::new();
-    ops.iter().for_each(|op| grid.apply_op(op));
-    let silver = grid.count_lights_on();
+    let program = parse_program(&data).unwrap_or_else(|e| panic!("Invalid instructions: {}", e));
+    for instruction in &program {
+        instruction.check(width, height, bounds).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    // Every instruction that covered light X,Y, with its value before and after.
+    if let Some(light) = arg_value(&args, "--why") {
+        let (x, y) = light.split_once(',')
+            .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            .expect("--why expects X,Y");
+        let on_off = provenance::<GridBits>(&program, width, height, x, y);
+        let brightness = provenance::<GridBrightness>(&program, width, height, x, y);
+        for (on, bright) in on_off.iter().zip(&brightness) {
+            println!("{:>4} {}: {} -> {}, brightness {} -> {}",
+                     on.index + 1, program[on.index], on.before, on.after, bright.before, bright.after);
+        }
+        return;
+    }
+
+    // --trace lists what each instruction changed; --after K stops after the first K instructions.
+    if args.iter().any(|a| a == "--trace") || arg_value(&args, "--after").is_some() {
+        let after = arg_value(&args, "--after").map_or(program.len(), |k| k.parse().expect("--after expects a number"));
+        let mut lights = Simulator::<GridBits>::new(&program, width, height);
+        let mut brightness = Simulator::<GridBrightness>::new(&program, width, height);
+        lights.jump_to(after);
+        brightness.jump_to(after);
+        if args.iter().any(|a| a == "--trace") {
+            for (instruction, (on, bright)) in program.iter().zip(lights.steps().iter().zip(brightness.steps())) {
+                println!("{:>4} {}: {} lights changed ({:+} on), {} brightness changed ({:+})",
+                         on.index + 1, instruction, on.changed, on.delta, bright.changed, bright.delta);
+            }
+        }
+        println!("After {} instructions: {} on, brightness {}",
+                 lights.position(), lights.grid().count_lights_on(), brightness.grid().count_brightness());
+        return;
+    }
+
+    // Only the rectangle edges matter, so huge grids can be solved without allocating them.
+    if args.iter().any(|a| a == "--compressed") {
+        let Some(ops) = program.iter().map(|instruction| instruction.grid_ops(width, height)).collect::<Option<Vec<_>>>() else {
+            eprintln!("--compressed cannot run copy instructions, which read the lights; try --sparse instead");
+            process::exit(1);
+        };
+        let ops: Vec<GridOp> = ops.into_iter().flatten().collect();
+        println!("Silver: {}", solve_compressed::<GridOnOff>(&ops, width, height));
+        println!("Gold: {}", solve_compressed::<GridBrightness>(&ops, width, height));
+        return;
+    }
+
+    // Lights kept as runs of equal values; paints and copies work on whole runs, so huge grids stay cheap.
+    if args.iter().any(|a| a == "--sparse") {
+        let (mut lights, mut brightness) = (SparseOnOff::new(width, height), SparseBrightness::new(width, height));
+        for instruction in &program {
+            instruction.apply(&mut lights);
+            instruction.apply(&mut brightness);
+        }
+        println!("Silver: {}", lights.total());
+        println!("Gold: {}", brightness.total());
+        return;
+    }
+
+    let queries = arg_value(&args, "--queries").map(|path| {
+        let text = std::fs::read_to_string(path).expect("Failed to read query file");
+        parse_rects(&text).unwrap_or_else(|e| panic!("Invalid queries: {}", e))
+    });
+
+    let (silver, lights) = if args.iter().any(|a| a == "--packed") {
+        let grid: GridBits = execute(&program, width, height, threads);
+        save_image_arg(&args, "--on-off-image", &grid);
+        (grid.count_lights_on(), queries.as_ref().map(|_| SummedArea::new(&grid)))
+    } else {
+        let grid: GridOnOff = execute(&program, width, height, threads);
+        save_image_arg(&args, "--on-off-image", &grid);
+        (grid.count_lights_on(), queries.as_ref().map(|_| SummedArea::new(&grid)))
+    };
     println!("Silver: {}", silver);
 
-    let mut grid2 = GridBrightness::new();
-    ops.iter().for_each(|op| grid2.apply_op(op));
+    let grid2: GridBrightness = execute(&program, width, height, threads);
+    save_image_arg(&args, "--brightness-image", &grid2);
     let gold = grid2.count_brightness();
     println!("Gold: {}", gold);
-}
-
-#[cfg(test)]
-mod tests
-{
-    use super::*;
-    #[test]
-    fn test_turn_on_all()
-    {
-        let mut grid = GridOnOff::new();
-        grid.apply_op(&GridOp::new("turn on", "0", "0", "999", "999"));
-        assert_eq!(grid.count_lights_on(), <GridOnOff as GridLike>::GRID_SIZE * <GridOnOff as GridLike>::GRID_SIZE);
-    }
 
-    #[test]
-    fn test_toggle_1st_row()
-    {
-        let mut grid = GridOnOff::new();
-        grid.apply_op(&GridOp::new("toggle", "0", "0", "999", "0"));
-        assert_eq!(grid.count_lights_on(), <GridOnOff as GridLike>::GRID_SIZE);
-    }
-
-    #[test]
-    fn test_turn_on_middle_4()
-    {
-        let mut grid = GridOnOff::new();
-        grid.apply_op(&GridOp::new("turn on", "499", "499", "500", "500"));
-        assert_eq!(grid.count_lights_on(), 4);
-    }
-
-    #[test]
-    fn test_turn_bright_1()
-    {
-        let mut grid = GridBrightness::new();
-        grid.apply_op(&GridOp::new("turn on", "0", "0", "0", "0"));
-        assert_eq!(grid.count_brightness(),  1);
+    // One line per rectangle in the --queries file: lights on and total brightness.
+    if let (Some(queries), Some(lights)) = (queries, lights) {
+        let brightness = SummedArea::new(&grid2);
+        for (x0, y0, x1, y1) in queries {
+            let on = lights.query(x0, y0, x1, y1, bounds).unwrap_or_else(|e| panic!("{}", e));
+            let total = brightness.query(x0, y0, x1, y1, bounds).unwrap_or_else(|e| panic!("{}", e));
+            println!("{},{} through {},{}: {} on, brightness {}", x0, y0, x1, y1, on, total);
+        }
     }
 
-    #[test]
-    fn test_turn_bright_all()
-    {
-        let mut grid = GridBrightness::new();
-        grid.apply_op(&GridOp::new("turn on", "0", "0", "999", "999"));
-        assert_eq!(grid.count_brightness(), <GridBrightness as GridLike>::GRID_SIZE * <GridBrightness as GridLike>::GRID_SIZE);
+    // One frame per --every instructions (default 1), on/off lights unless --animate-brightness.
+    if let Some(path) = arg_value(&args, "--animation") {
+        let every = arg_value(&args, "--every").map_or(1, |n| n.parse().expect("--every expects a number"));
+        let mut out = BufWriter::new(File::create(path).expect("Failed to create animation file"));
+        let frames = if args.iter().any(|a| a == "--animate-brightness") {
+            write_animation::<GridBrightness>(&mut out, &program, width, height, every)
+        } else {
+            write_animation::<GridBits>(&mut out, &program, width, height, every)
+        };
+        let frames = frames.and_then(|n| out.flush().map(|_| n)).unwrap_or_else(|e| panic!("Failed to write {}: {}", path, e));
+        eprintln!("Wrote {} frames to {}", frames, path);
     }
-}
\ No newline at end of file
+}
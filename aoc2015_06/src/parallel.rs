@@ -0,0 +1,172 @@
+// Runs a program on horizontal bands of rows in parallel. Painting a light
+// never reads another light, so each band applies every paint instruction
+// restricted to its own rows. A copy can read across bands, so the program is
+// cut at each copy: the paint runs in between go in parallel and the copy
+// itself runs through GridLike::copy_rect on the bands seen as one grid.
+
+use std::ops::Range;
+use std::thread;
+
+use crate::instruction::Instruction;
+use crate::GridLike;
+
+struct Band<G> {
+    rows: Range<usize>,
+    grid: G,
+}
+
+fn bands<G: GridLike>(width: usize, height: usize, threads: usize) -> Vec<Band<G>> {
+    let rows_per_band = height.div_ceil(threads.max(1)).max(1);
+    (0..height).step_by(rows_per_band)
+        .map(|start| {
+            let rows = start..(start + rows_per_band).min(height);
+            Band { grid: G::new(width, rows.len()), rows }
+        })
+        .collect()
+}
+
+fn paint<G: GridLike>(band: &mut Band<G>, program: &[Instruction], width: usize, height: usize) {
+    for instruction in program {
+        for mut op in instruction.grid_ops(width, height).unwrap_or_default() {
+            let (y0, y1) = (op.y0.max(band.rows.start), op.y1.min(band.rows.end - 1));
+            if y0 <= y1 {
+                (op.y0, op.y1) = (y0 - band.rows.start, y1 - band.rows.start);
+                band.grid.apply_op(&op);
+            }
+        }
+    }
+}
+
+fn band_of<G>(bands: &[Band<G>], y: usize) -> usize {
+    bands.partition_point(|band| band.rows.end <= y)
+}
+
+// Every band as one grid, each light read from or written to its band.
+struct Banded<G> {
+    width: usize,
+    height: usize,
+    bands: Vec<Band<G>>,
+}
+
+impl<G: GridLike> Banded<G>
+{
+    fn split(width: usize, height: usize, threads: usize) -> Self {
+        Banded { width, height, bands: bands(width, height, threads) }
+    }
+
+    fn band_mut(&mut self, y: usize) -> (&mut G, usize) {
+        let index = band_of(&self.bands, y);
+        let band = &mut self.bands[index];
+        (&mut band.grid, y - band.rows.start)
+    }
+}
+
+impl<G: GridLike> GridLike for Banded<G>
+{
+    fn new(width: usize, height: usize) -> Self {
+        Banded::split(width, height, 1)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn turn_on(&mut self, x: usize, y: usize) {
+        let (grid, y) = self.band_mut(y);
+        grid.turn_on(x, y);
+    }
+
+    fn turn_off(&mut self, x: usize, y: usize) {
+        let (grid, y) = self.band_mut(y);
+        grid.turn_off(x, y);
+    }
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        let (grid, y) = self.band_mut(y);
+        grid.toggle(x, y);
+    }
+
+    fn light(&self, x: usize, y: usize) -> usize {
+        let band = &self.bands[band_of(&self.bands, y)];
+        band.grid.light(x, y - band.rows.start)
+    }
+
+    fn set_light(&mut self, x: usize, y: usize, value: usize) {
+        let (grid, y) = self.band_mut(y);
+        grid.set_light(x, y, value);
+    }
+}
+
+// The grid after `program`, identical to applying it in order on one thread.
+pub fn execute<G: GridLike + Send>(program: &[Instruction], width: usize, height: usize, threads: usize) -> G
+{
+    if threads <= 1 || height <= 1 {
+        let mut grid = G::new(width, height);
+        program.iter().for_each(|instruction| instruction.apply(&mut grid));
+        return grid;
+    }
+
+    let mut banded = Banded::<G>::split(width, height, threads);
+    for run in program.split_inclusive(|instruction| matches!(instruction, Instruction::Copy { .. })) {
+        let (copy_instruction, paints) = match run.split_last() {
+            Some((last @ Instruction::Copy { .. }, paints)) => (Some(last), paints),
+            _ => (None, run),
+        };
+        thread::scope(|scope| {
+            for band in banded.bands.iter_mut() {
+                scope.spawn(move || paint(band, paints, width, height));
+            }
+        });
+        if let Some(instruction) = copy_instruction {
+            instruction.apply(&mut banded);
+        }
+    }
+
+    let mut grid = G::new(width, height);
+    for band in &banded.bands {
+        for y in band.rows.clone() {
+            for x in 0..width {
+                grid.set_light(x, y, band.grid.light(x, y - band.rows.start));
+            }
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bitgrid::GridBits;
+    use crate::testing::{program, replay, snapshot, HEIGHT, WIDTH};
+    use crate::{GridBrightness, GridOnOff};
+
+    fn check<G: GridLike + Send>(program: &[Instruction]) {
+        let expected = snapshot(&replay::<G>(program, WIDTH, HEIGHT));
+        for threads in [1, 2, 3, 4, 7, 23, 64] {
+            assert_eq!(snapshot(&execute::<G>(program, WIDTH, HEIGHT, threads)), expected, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn test_matches_sequential()
+    {
+        let program = program();
+        check::<GridOnOff>(&program);
+        check::<GridBrightness>(&program);
+        check::<GridBits>(&program);
+    }
+
+    #[test]
+    fn test_bands()
+    {
+        let rows: Vec<Range<usize>> = bands::<GridOnOff>(5, 10, 4).into_iter().map(|band| band.rows).collect();
+        assert_eq!(rows, [0..3, 3..6, 6..9, 9..10]);
+        let bands = bands::<GridOnOff>(5, 10, 4);
+        assert_eq!([0, 2, 3, 8, 9].map(|y| band_of(&bands, y)), [0, 0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,223 @@
+// Parser for light programs, one instruction per line; blank lines and lines
+// starting with '#' are ignored.
+//
+//   instruction := paint | "copy" rect "to" point
+//   paint       := "turn on" shape | "turn off" shape | "toggle" shape
+//                | "set" shape "to" number | "dim" shape "by" number
+//   shape       := rect | "outside" rect | "circle" point "radius" number
+//                | "line" point "to" point
+//   rect        := point "through" point
+//   point       := number "," number
+//
+// Query files use the rect rule alone.
+//
+// "turn on 0,0 through 999,999" is both an original instruction and a
+// rectangle in this language.
+
+use crate::instruction::{Instruction, Shape};
+use crate::OpCode;
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a>
+{
+    fn new(spaced: &'a str) -> Parser<'a> {
+        Parser { tokens: spaced.split_whitespace().collect(), pos: 0 }
+    }
+
+    fn finish<T>(&self, parsed: T, line: &str) -> Result<T, String> {
+        match self.peek() {
+            None => Ok(parsed),
+            Some(token) => Err(format!("Unexpected '{}' in '{}'", token, line)),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.peek().ok_or("Unexpected end of line")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == keyword => Ok(()),
+            token => Err(format!("Expected '{}', found '{}'", keyword, token)),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.next()?;
+        token.parse::<usize>().map_err(|_| format!("Expected a number, found '{}'", token))
+    }
+
+    fn point(&mut self) -> Result<(usize, usize), String> {
+        let x = self.number()?;
+        self.expect(",")?;
+        Ok((x, self.number()?))
+    }
+
+    fn rect(&mut self) -> Result<(usize, usize, usize, usize), String> {
+        let (x0, y0) = self.point()?;
+        self.expect("through")?;
+        let (x1, y1) = self.point()?;
+        Ok((x0, y0, x1, y1))
+    }
+
+    fn shape(&mut self) -> Result<Shape, String> {
+        match self.peek() {
+            Some("outside") => {
+                self.pos += 1;
+                let (x0, y0, x1, y1) = self.rect()?;
+                Ok(Shape::Outside { x0, y0, x1, y1 })
+            }
+            Some("circle") => {
+                self.pos += 1;
+                let (x, y) = self.point()?;
+                self.expect("radius")?;
+                Ok(Shape::Circle { x, y, radius: self.number()? })
+            }
+            Some("line") => {
+                self.pos += 1;
+                let (x0, y0) = self.point()?;
+                self.expect("to")?;
+                let (x1, y1) = self.point()?;
+                Ok(Shape::Line { x0, y0, x1, y1 })
+            }
+            _ => {
+                let (x0, y0, x1, y1) = self.rect()?;
+                Ok(Shape::Rect { x0, y0, x1, y1 })
+            }
+        }
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, String> {
+        match self.next()? {
+            "turn" => match self.next()? {
+                "on" => Ok(Instruction::Paint(OpCode::On, self.shape()?)),
+                "off" => Ok(Instruction::Paint(OpCode::Off, self.shape()?)),
+                token => Err(format!("Expected 'on' or 'off', found '{}'", token)),
+            },
+            "toggle" => Ok(Instruction::Paint(OpCode::Toggle, self.shape()?)),
+            "set" => {
+                let shape = self.shape()?;
+                self.expect("to")?;
+                Ok(Instruction::Paint(OpCode::Set(self.number()?), shape))
+            }
+            "dim" => {
+                let shape = self.shape()?;
+                self.expect("by")?;
+                Ok(Instruction::Paint(OpCode::Dim(self.number()?), shape))
+            }
+            "copy" => {
+                let (x0, y0, x1, y1) = self.rect()?;
+                self.expect("to")?;
+                let (x, y) = self.point()?;
+                Ok(Instruction::Copy { x0, y0, x1, y1, x, y })
+            }
+            token => Err(format!("Unknown instruction '{}'", token)),
+        }
+    }
+}
+
+impl Instruction
+{
+    pub fn parse(line: &str) -> Result<Instruction, String> {
+        let spaced = line.replace(',', " , ");
+        let mut parser = Parser::new(&spaced);
+        let instruction = parser.instruction()?;
+        parser.finish(instruction, line)
+    }
+}
+
+// A bare "x0,y0 through x1,y1" rectangle.
+pub fn parse_rect(line: &str) -> Result<(usize, usize, usize, usize), String>
+{
+    let spaced = line.replace(',', " , ");
+    let mut parser = Parser::new(&spaced);
+    let rect = parser.rect()?;
+    parser.finish(rect, line)
+}
+
+fn parse_lines<T>(text: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String>
+{
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| (n, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| parse(line).map_err(|e| format!("Line {}: {}", n + 1, e)))
+        .collect()
+}
+
+pub fn parse_program(text: &str) -> Result<Vec<Instruction>, String>
+{
+    parse_lines(text, Instruction::parse)
+}
+
+// Query files hold one rectangle per line, with the same comments and blank lines.
+pub fn parse_rects(text: &str) -> Result<Vec<(usize, usize, usize, usize)>, String>
+{
+    parse_lines(text, parse_rect)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::GridOp;
+
+    #[test]
+    fn test_original_instructions()
+    {
+        let program = parse_program("turn on 0,0 through 999,999\ntoggle 0,0 through 999,0\r\n\nturn off 499,499 through 500,500\n").unwrap();
+        assert_eq!(program, [
+            GridOp::new("turn on", "0", "0", "999", "999").into(),
+            GridOp::new("toggle", "0", "0", "999", "0").into(),
+            GridOp::new("turn off", "499", "499", "500", "500").into(),
+        ]);
+    }
+
+    #[test]
+    fn test_round_trip()
+    {
+        let text = [
+            "set 1,2 through 3,4 to 9",
+            "dim outside 0,0 through 5,5 by 2",
+            "toggle outside 10,10 through 20,20",
+            "turn on circle 50,60 radius 7",
+            "turn off line 0,9 to 9,0",
+            "set line 1,1 to 2,2 to 0",
+            "copy 0,0 through 9,9 to 100,200",
+        ];
+        for line in text {
+            assert_eq!(Instruction::parse(line).unwrap().to_string(), line);
+        }
+        assert_eq!(Instruction::parse("set circle 1,1 radius 2 to 3").unwrap(),
+                   Instruction::Paint(OpCode::Set(3), Shape::Circle { x: 1, y: 1, radius: 2 }));
+    }
+
+    #[test]
+    fn test_errors()
+    {
+        assert_eq!(Instruction::parse("turn sideways 0,0 through 1,1"), Err("Expected 'on' or 'off', found 'sideways'".to_string()));
+        assert_eq!(Instruction::parse("flip 0,0 through 1,1"), Err("Unknown instruction 'flip'".to_string()));
+        assert!(Instruction::parse("set 0,0 through 1,1").is_err());
+        assert!(Instruction::parse("toggle 0,0 through 1").is_err());
+        assert!(Instruction::parse("toggle 0,0 through 1,1 now").is_err());
+        assert_eq!(parse_program("# lights\ntoggle 0,0 through 1,1\ndim 0,0 by 1\n").unwrap_err(),
+                   "Line 3: Expected 'through', found 'by'");
+    }
+
+    #[test]
+    fn test_rects()
+    {
+        assert_eq!(parse_rects("0,0 through 9,9\n\n# corner\n 5,6 through 7,8 \n"), Ok(vec![(0, 0, 9, 9), (5, 6, 7, 8)]));
+        assert_eq!(parse_rects("0,0 through 9,9 extra"), Err("Line 1: Unexpected 'extra' in '0,0 through 9,9 extra'".to_string()));
+    }
+}
@@ -0,0 +1,138 @@
+// Why a light ended up the way it did, without replaying the grid. A light
+// only depends on the instructions that cover it, so it is followed through
+// the program on a 1x1 grid of the same kind. A copy sets the light from its
+// source light as it was just before the copy, which is found the same way.
+
+use crate::instruction::Instruction;
+use crate::{GridLike, GridOp};
+
+// An instruction that covered the light, with the light's value around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    pub index: usize,
+    pub before: usize,
+    pub after: usize,
+}
+
+fn covers(instruction: &Instruction, x: usize, y: usize, width: usize, height: usize) -> bool {
+    instruction.footprint(width, height).iter().any(|&(x0, y0, x1, y1)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y))
+}
+
+// The light a copy writes into (x, y), if it writes there at all.
+fn copy_source(instruction: &Instruction, x: usize, y: usize, width: usize, height: usize) -> Option<(usize, usize)> {
+    let Instruction::Copy { x0, y0, x1, y1, x: to_x, y: to_y } = *instruction else {
+        return None;
+    };
+    if x < to_x || y < to_y || x >= width || y >= height {
+        return None;
+    }
+    let (source_x, source_y) = (x0 + x - to_x, y0 + y - to_y);
+    let in_source = source_x <= x1.min(width - 1) && source_y <= y1.min(height - 1);
+    in_source.then_some((source_x, source_y))
+}
+
+// Applies a paint instruction to the one light of `cell` if it covers (x, y).
+fn paint<G: GridLike>(cell: &mut G, instruction: &Instruction, x: usize, y: usize, width: usize, height: usize) -> bool {
+    match *instruction {
+        Instruction::Paint(op_code, _) if covers(instruction, x, y, width, height) => {
+            cell.apply_op(&GridOp { op_code, x0: 0, y0: 0, x1: 0, y1: 0 });
+            true
+        }
+        _ => false,
+    }
+}
+
+// Value of light (x, y) after `program`. Only the last copy into the light
+// matters, so each copy in a chain is followed once.
+pub fn light_after<G: GridLike>(program: &[Instruction], width: usize, height: usize, x: usize, y: usize) -> usize
+{
+    let mut cell = G::new(1, 1);
+    let last_copy = program.iter().enumerate().rev()
+        .find_map(|(index, instruction)| copy_source(instruction, x, y, width, height).map(|source| (index, source)));
+    let first = match last_copy {
+        Some((index, (source_x, source_y))) => {
+            cell.set_light(0, 0, light_after::<G>(&program[..index], width, height, source_x, source_y));
+            index + 1
+        }
+        None => 0,
+    };
+    for instruction in &program[first..] {
+        paint(&mut cell, instruction, x, y, width, height);
+    }
+    cell.light(0, 0)
+}
+
+// Every instruction covering light (x, y), in program order.
+pub fn provenance<G: GridLike>(program: &[Instruction], width: usize, height: usize, x: usize, y: usize) -> Vec<Touch>
+{
+    let mut cell = G::new(1, 1);
+    let mut touches = Vec::new();
+    for (index, instruction) in program.iter().enumerate() {
+        let before = cell.light(0, 0);
+        let touched = match copy_source(instruction, x, y, width, height) {
+            Some((source_x, source_y)) => {
+                cell.set_light(0, 0, light_after::<G>(&program[..index], width, height, source_x, source_y));
+                true
+            }
+            None => paint(&mut cell, instruction, x, y, width, height),
+        };
+        if touched {
+            touches.push(Touch { index, before, after: cell.light(0, 0) });
+        }
+    }
+    touches
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parse::parse_program;
+    use crate::testing::{program, snapshot, HEIGHT, WIDTH};
+    use crate::{GridBrightness, GridOnOff};
+
+    fn check<G: GridLike>(program: &[Instruction]) {
+        let mut grid = G::new(WIDTH, HEIGHT);
+        let mut states = vec![];
+        for instruction in program {
+            states.push(snapshot(&grid));
+            instruction.apply(&mut grid);
+        }
+        for x in 0..WIDTH {
+            for y in 0..HEIGHT {
+                assert_eq!(light_after::<G>(program, WIDTH, HEIGHT, x, y), grid.light(x, y), "{},{}", x, y);
+                let touches = provenance::<G>(program, WIDTH, HEIGHT, x, y);
+                let mut value = 0;
+                for touch in &touches {
+                    assert_eq!(touch.before, value);
+                    assert_eq!(touch.before, states[touch.index][x * HEIGHT + y]);
+                    value = touch.after;
+                }
+                assert_eq!(value, grid.light(x, y), "{},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_full_replay()
+    {
+        let program = program();
+        check::<GridOnOff>(&program);
+        check::<GridBrightness>(&program);
+    }
+
+    #[test]
+    fn test_touches()
+    {
+        let program = parse_program("turn on 0,0 through 3,3\ntoggle 2,2 through 5,5\ncopy 0,0 through 1,1 to 4,4\nturn on 9,9 through 9,9\n").unwrap();
+        assert_eq!(provenance::<GridBrightness>(&program, 10, 10, 2, 2), [
+            Touch { index: 0, before: 0, after: 1 },
+            Touch { index: 1, before: 1, after: 3 },
+        ]);
+        assert_eq!(provenance::<GridOnOff>(&program, 10, 10, 5, 5), [
+            Touch { index: 1, before: 0, after: 1 },
+            Touch { index: 2, before: 1, after: 1 },
+        ]);
+        assert_eq!(provenance::<GridOnOff>(&program, 10, 10, 8, 0), []);
+    }
+}
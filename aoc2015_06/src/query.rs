@@ -0,0 +1,98 @@
+// Rectangle sums over a finished grid. The summed-area table holds, for every
+// corner (x, y), the total of all lights above and to the left of it, so any
+// rectangle total is four lookups after one pass over the grid.
+
+use crate::{Bounds, GridLike};
+
+pub struct SummedArea {
+    width: usize,
+    height: usize,
+    // (width + 1) x (height + 1) corners, row by row, with a zero first row and column.
+    sums: Vec<usize>,
+}
+
+impl SummedArea
+{
+    pub fn new<G: GridLike>(grid: &G) -> SummedArea {
+        let (width, height) = (grid.width(), grid.height());
+        let stride = width + 1;
+        let mut sums = vec![0; stride * (height + 1)];
+        for y in 0..height {
+            let mut row = 0;
+            for x in 0..width {
+                row += grid.light(x, y);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+            }
+        }
+        SummedArea { width, height, sums }
+    }
+
+    fn corner(&self, x: usize, y: usize) -> usize {
+        self.sums[y * (self.width + 1) + x]
+    }
+
+    // Lights on for an on/off grid, total brightness otherwise, over the part
+    // of the rectangle that lies on the grid.
+    pub fn sum(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> usize {
+        let (x1, y1) = (x1.saturating_add(1).min(self.width), y1.saturating_add(1).min(self.height));
+        if x0 >= x1 || y0 >= y1 {
+            return 0;
+        }
+        self.corner(x1, y1) + self.corner(x0, y0) - self.corner(x0, y1) - self.corner(x1, y0)
+    }
+
+    pub fn query(&self, x0: usize, y0: usize, x1: usize, y1: usize, bounds: Bounds) -> Result<usize, String> {
+        if bounds == Bounds::Strict && (x1 >= self.width || y1 >= self.height) {
+            return Err(format!("{},{} through {},{} is outside the {}x{} grid", x0, y0, x1, y1, self.width, self.height));
+        }
+        Ok(self.sum(x0, y0, x1, y1))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parse::parse_program;
+    use crate::{GridBrightness, GridOnOff};
+
+    fn grids() -> (GridOnOff, GridBrightness) {
+        let program = parse_program("turn on circle 20,12 radius 9\ntoggle 5,3 through 33,17\ndim line 0,0 to 39,24 by 1\n").unwrap();
+        let (mut on_off, mut brightness) = (GridOnOff::new(40, 25), GridBrightness::new(40, 25));
+        for instruction in &program {
+            instruction.apply(&mut on_off);
+            instruction.apply(&mut brightness);
+        }
+        (on_off, brightness)
+    }
+
+    fn brute_force<G: GridLike>(grid: &G, x0: usize, y0: usize, x1: usize, y1: usize) -> usize {
+        (x0..=x1).flat_map(|x| (y0..=y1).map(move |y| (x, y))).map(|(x, y)| grid.light(x, y)).sum()
+    }
+
+    #[test]
+    fn test_matches_brute_force()
+    {
+        let (on_off, brightness) = grids();
+        let (lights, total) = (SummedArea::new(&on_off), SummedArea::new(&brightness));
+        for (x0, y0, x1, y1) in [(0, 0, 39, 24), (3, 4, 3, 4), (10, 0, 30, 12), (0, 7, 39, 7), (17, 9, 38, 23)] {
+            assert_eq!(lights.sum(x0, y0, x1, y1), brute_force(&on_off, x0, y0, x1, y1));
+            assert_eq!(total.sum(x0, y0, x1, y1), brute_force(&brightness, x0, y0, x1, y1));
+        }
+        assert_eq!(lights.sum(0, 0, 39, 24), on_off.count_lights_on());
+        assert_eq!(total.sum(0, 0, 39, 24), brightness.count_brightness());
+    }
+
+    #[test]
+    fn test_bounds()
+    {
+        let (_, brightness) = grids();
+        let total = SummedArea::new(&brightness);
+        assert_eq!(total.sum(30, 20, 500, 500), total.sum(30, 20, 39, 24));
+        assert_eq!(total.sum(40, 0, 50, 5), 0);
+        assert_eq!(total.sum(5, 5, 4, 9), 0);
+        assert_eq!(total.sum(0, 0, usize::MAX, usize::MAX), total.sum(0, 0, 39, 24));
+        assert_eq!(total.query(30, 20, 500, 500, Bounds::Clip), Ok(total.sum(30, 20, 39, 24)));
+        assert_eq!(total.query(30, 20, 500, 500, Bounds::Strict), Err("30,20 through 500,500 is outside the 40x25 grid".to_string()));
+    }
+}
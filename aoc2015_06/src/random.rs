@@ -0,0 +1,35 @@
+// Xorshift64 for reproducible test programs and benchmark workloads. Only
+// built for tests; the benchmarks include this file by path.
+
+use crate::{GridOp, OpCode};
+
+pub struct Rng(u64);
+
+impl Rng
+{
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// Turn on, turn off and toggle rectangles anywhere on a width x height grid.
+pub fn random_ops(rng: &mut Rng, count: usize, width: usize, height: usize) -> Vec<GridOp>
+{
+    (0..count).map(|_| {
+        let op_code = [OpCode::On, OpCode::Off, OpCode::Toggle][rng.below(3)];
+        let (x0, y0) = (rng.below(width), rng.below(height));
+        let (x1, y1) = (x0 + rng.below(width - x0), y0 + rng.below(height - y0));
+        GridOp { op_code, x0, y0, x1, y1 }
+    }).collect()
+}
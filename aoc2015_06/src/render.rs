@@ -0,0 +1,184 @@
+// Pictures of the light grid, one pixel per light with x to the right and y
+// down. Pixels are scaled so the brightest light in view is white: on/off
+// grids come out black and white, brightness grids as grayscale.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::instruction::Instruction;
+use crate::GridLike;
+
+const FRAME_DELAY: u16 = 5;
+
+pub fn peak<G: GridLike>(grid: &G) -> usize
+{
+    (0..grid.width())
+        .flat_map(|x| (0..grid.height()).map(move |y| grid.light(x, y)))
+        .max()
+        .unwrap_or(0)
+}
+
+// Row-major gray levels, with `peak` mapped to 255.
+pub fn grayscale<G: GridLike>(grid: &G, peak: usize) -> Vec<u8>
+{
+    let (width, height) = (grid.width(), grid.height());
+    let mut pixels = vec![0; width * height];
+    if peak == 0 {
+        return pixels;
+    }
+    for y in 0..height {
+        for x in 0..width {
+            pixels[y * width + x] = (grid.light(x, y).min(peak) * 255 / peak) as u8;
+        }
+    }
+    pixels
+}
+
+pub fn write_pgm(out: &mut dyn Write, width: usize, height: usize, pixels: &[u8]) -> io::Result<()>
+{
+    write!(out, "P5\n{} {}\n255\n", width, height)?;
+    out.write_all(pixels)
+}
+
+pub fn write_png(out: &mut dyn Write, width: usize, height: usize, pixels: &[u8]) -> io::Result<()>
+{
+    let (width, height) = (dimension::<u32>(width)?, dimension::<u32>(height)?);
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(())
+}
+
+// PNG or PGM, picked by the file extension.
+pub fn save_image<G: GridLike>(path: &Path, grid: &G) -> io::Result<()>
+{
+    let pixels = grayscale(grid, peak(grid));
+    let mut out = BufWriter::new(File::create(path)?);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => write_png(&mut out, grid.width(), grid.height(), &pixels)?,
+        Some("pgm") => write_pgm(&mut out, grid.width(), grid.height(), &pixels)?,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a .png or .pgm file", path.display()))),
+    }
+    out.flush()
+}
+
+fn dimension<T: TryFrom<usize>>(len: usize) -> io::Result<T> {
+    T::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{} pixels is too large for the image format", len)))
+}
+
+// Grid states worth a frame: after every `every` instructions and after the last.
+fn frames<G: GridLike>(program: &[Instruction], width: usize, height: usize, every: usize, mut f: impl FnMut(&G)) {
+    let every = every.max(1);
+    let mut grid = G::new(width, height);
+    for (i, instruction) in program.iter().enumerate() {
+        instruction.apply(&mut grid);
+        if (i + 1) % every == 0 || i + 1 == program.len() {
+            f(&grid);
+        }
+    }
+}
+
+// An animated GIF of the instructions. The gray scale is fixed by the
+// brightest light of any frame so frames stay comparable, which takes a first
+// pass over the instructions. Returns the frame count.
+pub fn write_animation<G: GridLike>(out: &mut dyn Write, program: &[Instruction], width: usize, height: usize, every: usize) -> io::Result<usize>
+{
+    let mut brightest = 0;
+    frames::<G>(program, width, height, every, |grid| brightest = brightest.max(peak(grid)));
+
+    let palette: Vec<u8> = (0..=255).flat_map(|level| [level; 3]).collect();
+    let (frame_width, frame_height) = (dimension::<u16>(width)?, dimension::<u16>(height)?);
+    let mut encoder = gif::Encoder::new(out, frame_width, frame_height, &palette).map_err(io::Error::other)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+
+    let mut count = 0;
+    let mut result = Ok(());
+    frames::<G>(program, width, height, every, |grid| {
+        if result.is_ok() {
+            let mut frame = gif::Frame::from_indexed_pixels(frame_width, frame_height, grayscale(grid, brightest), None);
+            frame.delay = FRAME_DELAY;
+            result = encoder.write_frame(&frame).map_err(io::Error::other);
+            count += 1;
+        }
+    });
+    result.map(|_| count)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parse::parse_program;
+    use crate::{GridBrightness, GridOnOff};
+
+    fn program() -> Vec<Instruction> {
+        parse_program("turn on 0,0 through 3,1\ntoggle 1,0 through 1,2\nturn off 3,0 through 4,2\n").unwrap()
+    }
+
+    #[test]
+    fn test_grayscale()
+    {
+        let mut on_off = GridOnOff::new(5, 3);
+        let mut brightness = GridBrightness::new(5, 3);
+        for instruction in program() {
+            instruction.apply(&mut on_off);
+            instruction.apply(&mut brightness);
+        }
+        assert_eq!(grayscale(&on_off, peak(&on_off)), [
+            255, 0, 255, 0, 0,
+            255, 0, 255, 0, 0,
+            0, 255, 0, 0, 0,
+        ]);
+        assert_eq!(peak(&brightness), 3);
+        assert_eq!(grayscale(&brightness, 3), [
+            85, 255, 85, 0, 0,
+            85, 255, 85, 0, 0,
+            0, 170, 0, 0, 0,
+        ]);
+        assert_eq!(grayscale(&GridOnOff::new(2, 2), 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_pgm()
+    {
+        let mut out = Vec::new();
+        write_pgm(&mut out, 2, 1, &[0, 255]).unwrap();
+        assert_eq!(out, b"P5\n2 1\n255\n\x00\xff");
+    }
+
+    #[test]
+    fn test_png_round_trip()
+    {
+        let pixels: Vec<u8> = (0..15).map(|i| i * 17).collect();
+        let mut out = Vec::new();
+        write_png(&mut out, 5, 3, &pixels).unwrap();
+        let mut reader = png::Decoder::new(out.as_slice()).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((info.width, info.height, info.color_type), (5, 3, png::ColorType::Grayscale));
+        assert_eq!(decoded, pixels);
+    }
+
+    #[test]
+    fn test_animation_frames()
+    {
+        for (every, expected) in [(1, 3), (2, 2), (5, 1)] {
+            let mut out = Vec::new();
+            assert_eq!(write_animation::<GridBrightness>(&mut out, &program(), 5, 3, every).unwrap(), expected);
+
+            let mut options = gif::DecodeOptions::new();
+            options.set_color_output(gif::ColorOutput::Indexed);
+            let mut decoder = options.read_info(out.as_slice()).unwrap();
+            let mut last = Vec::new();
+            let mut count = 0;
+            while let Some(frame) = decoder.read_next_frame().unwrap() {
+                last = frame.buffer.to_vec();
+                count += 1;
+            }
+            assert_eq!(count, expected);
+            assert_eq!(last, [85, 255, 85, 0, 0, 85, 255, 85, 0, 0, 0, 170, 0, 0, 0]);
+        }
+    }
+}
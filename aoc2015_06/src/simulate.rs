@@ -0,0 +1,180 @@
+// Steps through a program one instruction at a time, forwards and backwards.
+// Recording every changed light for undo would take gigabytes on a full-size
+// grid, so the simulator keeps a copy of the grid every `interval`
+// instructions instead: going back restores the nearest earlier copy and
+// replays the few instructions after it.
+
+use crate::instruction::Instruction;
+use crate::GridLike;
+
+pub const CHECKPOINT_INTERVAL: usize = 32;
+
+// What one instruction did: how many lights changed value, and the change in
+// the grid total (lights on, or total brightness).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub index: usize,
+    pub changed: usize,
+    pub delta: i64,
+}
+
+pub struct Simulator<'a, G: GridLike + Clone> {
+    program: &'a [Instruction],
+    grid: G,
+    position: usize,
+    interval: usize,
+    // checkpoints[i] is the grid after i * interval instructions.
+    checkpoints: Vec<G>,
+    // Steps of instructions 0..steps.len(), kept once measured.
+    steps: Vec<Step>,
+}
+
+impl<'a, G: GridLike + Clone> Simulator<'a, G>
+{
+    pub fn new(program: &'a [Instruction], width: usize, height: usize) -> Simulator<'a, G> {
+        Simulator::with_interval(program, width, height, CHECKPOINT_INTERVAL)
+    }
+
+    pub fn with_interval(program: &'a [Instruction], width: usize, height: usize, interval: usize) -> Simulator<'a, G> {
+        let grid = G::new(width, height);
+        let checkpoints = vec![grid.clone()];
+        Simulator { program, grid, position: 0, interval: interval.max(1), checkpoints, steps: Vec::new() }
+    }
+
+    pub fn grid(&self) -> &G {
+        &self.grid
+    }
+
+    // Number of instructions applied to the current grid.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.program.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.program.is_empty()
+    }
+
+    // Applies the next instruction, or redoes it after an undo.
+    pub fn forward(&mut self) -> Option<Step> {
+        let instruction = self.program.get(self.position)?;
+        let footprint = instruction.footprint(self.grid.width(), self.grid.height());
+        let before: Vec<usize> = lights(&self.grid, &footprint).collect();
+        instruction.apply(&mut self.grid);
+
+        let mut step = Step { index: self.position, changed: 0, delta: 0 };
+        for (old, new) in before.into_iter().zip(lights(&self.grid, &footprint)) {
+            if old != new {
+                step.changed += 1;
+                step.delta += new as i64 - old as i64;
+            }
+        }
+        self.position += 1;
+        if self.position.is_multiple_of(self.interval) && self.checkpoints.len() == self.position / self.interval {
+            self.checkpoints.push(self.grid.clone());
+        }
+        if self.steps.len() == step.index {
+            self.steps.push(step);
+        }
+        Some(step)
+    }
+
+    // Undoes the last instruction applied, returning what it had done.
+    pub fn back(&mut self) -> Option<Step> {
+        let index = self.position.checked_sub(1)?;
+        self.jump_to(index);
+        Some(self.steps[index])
+    }
+
+    // Moves to the grid after the first `k` instructions (all of them if k is
+    // past the end).
+    pub fn jump_to(&mut self, k: usize) {
+        let k = k.min(self.program.len());
+        if k < self.position {
+            let checkpoint = k / self.interval;
+            self.grid = self.checkpoints[checkpoint].clone();
+            self.position = checkpoint * self.interval;
+        }
+        while self.position < k {
+            self.forward();
+        }
+    }
+
+    // Steps of every instruction applied so far, including undone ones.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+fn lights<'g, G: GridLike>(grid: &'g G, footprint: &'g [(usize, usize, usize, usize)]) -> impl Iterator<Item = usize> + 'g {
+    footprint.iter().flat_map(move |&(x0, y0, x1, y1)| {
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| grid.light(x, y)))
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::testing::{program, replay, snapshot, HEIGHT, WIDTH};
+    use crate::{GridBrightness, GridOnOff};
+
+    #[test]
+    fn test_steps()
+    {
+        let program = program();
+        let mut simulator = Simulator::<GridBrightness>::new(&program, WIDTH, HEIGHT);
+        assert_eq!(simulator.forward(), Some(Step { index: 0, changed: 31 * 21, delta: 31 * 21 }));
+        // Toggling adds 2 to every light of the circle.
+        let circle: usize = program[1].footprint(WIDTH, HEIGHT).iter().map(|&(x0, y0, x1, y1)| (x1 - x0 + 1) * (y1 - y0 + 1)).sum();
+        assert_eq!(simulator.forward(), Some(Step { index: 1, changed: circle, delta: 2 * circle as i64 }));
+        simulator.jump_to(program.len());
+        assert_eq!(simulator.forward(), None);
+        let total: i64 = simulator.steps().iter().map(|step| step.delta).sum();
+        assert_eq!(total as usize, simulator.grid().count_brightness());
+
+        let mut simulator = Simulator::<GridOnOff>::new(&program, WIDTH, HEIGHT);
+        simulator.jump_to(program.len());
+        // The last two instructions toggle every light and cancel out.
+        let (first, second) = (simulator.steps()[7], simulator.steps()[8]);
+        assert_eq!((first.changed, second.changed), (WIDTH * HEIGHT, WIDTH * HEIGHT));
+        assert_eq!(first.delta, -second.delta);
+        let total: i64 = simulator.steps().iter().map(|step| step.delta).sum();
+        assert_eq!(total as usize, simulator.grid().count_lights_on());
+    }
+
+    #[test]
+    fn test_undo_redo()
+    {
+        let program = program();
+        for interval in [1, 2, 3, 100] {
+            let mut simulator = Simulator::<GridBrightness>::with_interval(&program, WIDTH, HEIGHT, interval);
+            simulator.jump_to(program.len());
+            for k in (0..program.len()).rev() {
+                let undone = simulator.back().unwrap();
+                assert_eq!(undone.index, k);
+                let expected = snapshot(&replay::<GridBrightness>(&program[..k], WIDTH, HEIGHT));
+                assert_eq!(snapshot(simulator.grid()), expected, "interval {}", interval);
+            }
+            assert_eq!(simulator.back(), None);
+            let redone = simulator.forward().unwrap();
+            assert_eq!(redone, simulator.steps()[0]);
+        }
+    }
+
+    #[test]
+    fn test_jump()
+    {
+        let program = program();
+        let mut simulator = Simulator::<GridOnOff>::with_interval(&program, WIDTH, HEIGHT, 2);
+        for k in [5, 1, 7, 3, 0, 100, 4] {
+            let k_applied = k.min(program.len());
+            simulator.jump_to(k);
+            assert_eq!(simulator.position(), k_applied);
+            assert_eq!(snapshot(simulator.grid()), snapshot(&replay::<GridOnOff>(&program[..k_applied], WIDTH, HEIGHT)));
+        }
+    }
+}
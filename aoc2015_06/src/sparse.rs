@@ -0,0 +1,324 @@
+// Lights stored as runs, for displays far too large to allocate. The grid is
+// a list of bands, each a run of identical rows starting at its key, and each
+// row is a list of (first x, value) runs covering the full width. An
+// instruction splits at most two bands, and two runs in each row it covers,
+// then merges neighbours that became equal again. Memory follows the number
+// of uniform regions instead of the number of lights.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use crate::{GridLike, GridOp, OpCode};
+
+// How an instruction changes one light, for each kind of grid.
+pub trait Lights
+{
+    fn update(op_code: OpCode, value: usize) -> usize;
+}
+
+#[derive(Clone)]
+pub struct OnOff;
+
+#[derive(Clone)]
+pub struct Brightness;
+
+impl Lights for OnOff
+{
+    fn update(op_code: OpCode, value: usize) -> usize {
+        match op_code {
+            OpCode::On => 1,
+            OpCode::Off => 0,
+            OpCode::Toggle => 1 - value,
+            OpCode::Set(level) => (level > 0) as usize,
+            OpCode::Dim(0) => value,
+            OpCode::Dim(_) => 0,
+        }
+    }
+}
+
+impl Lights for Brightness
+{
+    fn update(op_code: OpCode, value: usize) -> usize {
+        match op_code {
+            OpCode::On => value + 1,
+            OpCode::Off => value.saturating_sub(1),
+            OpCode::Toggle => value + 2,
+            OpCode::Set(level) => level,
+            OpCode::Dim(amount) => value.saturating_sub(amount),
+        }
+    }
+}
+
+type Row = Vec<(usize, usize)>;
+
+#[derive(Clone)]
+pub struct SparseGrid<L> {
+    width: usize,
+    height: usize,
+    bands: BTreeMap<usize, Row>,
+    lights: PhantomData<L>,
+}
+
+pub type SparseOnOff = SparseGrid<OnOff>;
+pub type SparseBrightness = SparseGrid<Brightness>;
+
+// Index of the run holding light x.
+fn run_of(row: &Row, x: usize) -> usize {
+    row.partition_point(|&(start, _)| start <= x) - 1
+}
+
+// Starts a run at x, unless one already starts there.
+fn split_row(row: &mut Row, x: usize) {
+    let run = run_of(row, x);
+    if row[run].0 != x {
+        row.insert(run + 1, (x, row[run].1));
+    }
+}
+
+impl<L: Lights> SparseGrid<L>
+{
+    fn row(&self, y: usize) -> &Row {
+        self.bands.range(..=y).next_back().expect("Row outside the grid").1
+    }
+
+    // Starts a band at y, unless one already starts there.
+    fn split_band(&mut self, y: usize) {
+        let (&start, row) = self.bands.range(..=y).next_back().expect("Row outside the grid");
+        if start != y {
+            let row = row.clone();
+            self.bands.insert(y, row);
+        }
+    }
+
+    // Removes bands in first..=last equal to the band above them.
+    fn merge_bands(&mut self, first: usize, last: usize) {
+        let keys: Vec<usize> = self.bands.range(first..=last).map(|(&y, _)| y).collect();
+        let Some((&(mut previous), rest)) = keys.split_first() else {
+            return;
+        };
+        for &y in rest {
+            if self.bands[&previous] == self.bands[&y] {
+                self.bands.remove(&y);
+            } else {
+                previous = y;
+            }
+        }
+    }
+
+    // Number of runs stored, over all bands.
+    pub fn runs(&self) -> usize {
+        self.bands.values().map(Vec::len).sum()
+    }
+
+    // Lights on for an on/off grid, total brightness otherwise.
+    pub fn total(&self) -> usize {
+        let mut total = 0;
+        let mut bands = self.bands.iter().peekable();
+        while let Some((&y, row)) = bands.next() {
+            let rows = bands.peek().map_or(self.height, |(&next, _)| next) - y;
+            let ends = row.iter().skip(1).map(|&(start, _)| start).chain([self.width]);
+            total += rows * row.iter().zip(ends).map(|(&(start, value), end)| (end - start) * value).sum::<usize>();
+        }
+        total
+    }
+}
+
+impl<L: Lights> GridLike for SparseGrid<L>
+{
+    fn new(width: usize, height: usize) -> Self {
+        let mut bands = BTreeMap::new();
+        if width > 0 && height > 0 {
+            bands.insert(0, vec![(0, 0)]);
+        }
+        SparseGrid { width, height, bands, lights: PhantomData }
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn turn_on(&mut self, x: usize, y: usize) {
+        self.apply_op(&GridOp { op_code: OpCode::On, x0: x, y0: y, x1: x, y1: y });
+    }
+
+    fn turn_off(&mut self, x: usize, y: usize) {
+        self.apply_op(&GridOp { op_code: OpCode::Off, x0: x, y0: y, x1: x, y1: y });
+    }
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        self.apply_op(&GridOp { op_code: OpCode::Toggle, x0: x, y0: y, x1: x, y1: y });
+    }
+
+    fn light(&self, x: usize, y: usize) -> usize {
+        let row = self.row(y);
+        row[run_of(row, x)].1
+    }
+
+    fn set_light(&mut self, x: usize, y: usize, value: usize) {
+        self.apply_op(&GridOp { op_code: OpCode::Set(value), x0: x, y0: y, x1: x, y1: y });
+    }
+
+    // Copies whole runs: the source is cut along its bands and runs, the
+    // destination is split on the same boundaries, and each destination row
+    // gets the matching source runs spliced in.
+    fn copy_rect(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, x: usize, y: usize)
+    {
+        if x0 > x1 || y0 > y1 || x0 >= self.width || y0 >= self.height || x >= self.width || y >= self.height {
+            return;
+        }
+        let width = (x1.min(self.width - 1) - x0 + 1).min(self.width - x);
+        let height = (y1.min(self.height - 1) - y0 + 1).min(self.height - y);
+
+        // The source as (first row, runs) with both relative to x0,y0, taken before any write.
+        let above = self.bands.range(..=y0).next_back().map_or(y0, |(&start, _)| start);
+        let source: Vec<(usize, Row)> = self.bands.range(above..y0 + height)
+            .map(|(&start, row)| {
+                let runs = row[run_of(row, x0)..].iter()
+                    .take_while(|run| run.0 < x0 + width)
+                    .map(|&(run_x, value)| (run_x.max(x0) - x0, value))
+                    .collect();
+                (start.max(y0) - y0, runs)
+            })
+            .collect();
+
+        for &(row, _) in &source {
+            self.split_band(y + row);
+        }
+        if y + height < self.height {
+            self.split_band(y + height);
+        }
+        for (&start, row) in self.bands.range_mut(y..y + height) {
+            let runs = &source[source.partition_point(|&(first, _)| first <= start - y) - 1].1;
+            split_row(row, x);
+            if x + width < self.width {
+                split_row(row, x + width);
+            }
+            let (first, end) = (run_of(row, x), row.partition_point(|&(run_x, _)| run_x < x + width));
+            row.splice(first..end, runs.iter().map(|&(run_x, value)| (x + run_x, value)));
+            row.dedup_by(|run, previous| run.1 == previous.1);
+        }
+        let above = self.bands.range(..y).next_back().map_or(y, |(&start, _)| start);
+        self.merge_bands(above, y + height);
+    }
+
+    fn apply_op(&mut self, op: &GridOp)
+    {
+        if op.x0 > op.x1 || op.y0 > op.y1 {
+            return;
+        }
+        self.split_band(op.y0);
+        if op.y1 + 1 < self.height {
+            self.split_band(op.y1 + 1);
+        }
+        for row in self.bands.range_mut(op.y0..=op.y1).map(|(_, row)| row) {
+            split_row(row, op.x0);
+            if op.x1 + 1 < self.width {
+                split_row(row, op.x1 + 1);
+            }
+            let first = run_of(row, op.x0);
+            for run in row[first..].iter_mut().take_while(|run| run.0 <= op.x1) {
+                run.1 = L::update(op.op_code, run.1);
+            }
+            row.dedup_by(|run, previous| run.1 == previous.1);
+        }
+        let above = self.bands.range(..op.y0).next_back().map_or(op.y0, |(&y, _)| y);
+        self.merge_bands(above, op.y1 + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::random::{random_ops, Rng};
+    use crate::testing::{program, snapshot, HEIGHT, WIDTH};
+    use crate::{GridBrightness, GridOnOff};
+
+    // No two neighbouring bands or runs are equal, so uniform regions are stored once.
+    fn assert_merged<L: Lights>(grid: &SparseGrid<L>) {
+        let rows: Vec<&Row> = grid.bands.values().collect();
+        assert!(rows.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(rows.iter().all(|row| row[0].0 == 0 && row.windows(2).all(|pair| pair[0].1 != pair[1].1)));
+    }
+
+    #[test]
+    fn test_matches_dense_grids()
+    {
+        let (mut on_off, mut brightness) = (GridOnOff::new(WIDTH, HEIGHT), GridBrightness::new(WIDTH, HEIGHT));
+        let (mut sparse_on_off, mut sparse_brightness) = (SparseOnOff::new(WIDTH, HEIGHT), SparseBrightness::new(WIDTH, HEIGHT));
+        for instruction in &program() {
+            instruction.apply(&mut on_off);
+            instruction.apply(&mut brightness);
+            instruction.apply(&mut sparse_on_off);
+            instruction.apply(&mut sparse_brightness);
+            assert_merged(&sparse_on_off);
+            assert_merged(&sparse_brightness);
+            assert_eq!(snapshot(&sparse_on_off), snapshot(&on_off));
+            assert_eq!(snapshot(&sparse_brightness), snapshot(&brightness));
+        }
+        assert_eq!(sparse_on_off.total(), on_off.count_lights_on());
+        assert_eq!(sparse_brightness.total(), brightness.count_brightness());
+    }
+
+    #[test]
+    fn test_copies_match_dense_grid()
+    {
+        let mut rng = Rng::new(50);
+        let (mut dense, mut sparse) = (GridBrightness::new(WIDTH, HEIGHT), SparseBrightness::new(WIDTH, HEIGHT));
+        for op in random_ops(&mut rng, 100, WIDTH, HEIGHT) {
+            dense.apply_op(&op);
+            sparse.apply_op(&op);
+            // Sources and destinations may overlap and run off the grid.
+            let (x0, y0) = (rng.below(WIDTH), rng.below(HEIGHT));
+            let (x1, y1, x, y) = (x0 + rng.below(WIDTH), y0 + rng.below(HEIGHT), rng.below(WIDTH), rng.below(HEIGHT));
+            dense.copy_rect(x0, y0, x1, y1, x, y);
+            sparse.copy_rect(x0, y0, x1, y1, x, y);
+            assert_merged(&sparse);
+            assert_eq!(snapshot(&sparse), snapshot(&dense), "copy {},{} through {},{} to {},{}", x0, y0, x1, y1, x, y);
+        }
+    }
+
+    #[test]
+    fn test_merges_back()
+    {
+        let mut grid = SparseBrightness::new(10, 10);
+        grid.apply_op(&GridOp::new("toggle", "2", "3", "7", "8"));
+        assert_eq!(grid.runs(), 5);
+        grid.apply_op(&GridOp::new("turn off", "2", "3", "7", "8"));
+        grid.apply_op(&GridOp::new("turn off", "2", "3", "7", "8"));
+        assert_eq!(grid.runs(), 1);
+        assert_eq!(grid.total(), 0);
+    }
+
+    #[test]
+    fn test_huge_grid()
+    {
+        let size = 1_000_000;
+        let mut on_off = SparseOnOff::new(size, size);
+        let mut brightness = SparseBrightness::new(size, size);
+        let ops = [
+            GridOp::new("turn on", "0", "0", "999999", "999999"),
+            GridOp::new("toggle", "1", "1", "999998", "999998"),
+            GridOp::new("turn off", "250000", "0", "749999", "499999"),
+        ];
+        for op in &ops {
+            on_off.apply_op(op);
+            brightness.apply_op(op);
+        }
+        assert_eq!(on_off.total(), 4 * (size - 1) - 500_000);
+        assert_eq!(brightness.total(), size * size + 2 * (size - 2) * (size - 2) - 500_000 * 500_000);
+        assert_eq!((on_off.light(0, 5), on_off.light(5, 5), brightness.light(300_000, 5)), (1, 0, 2));
+        assert!(on_off.runs() < 20 && brightness.runs() < 20);
+
+        // Copying most of the display moves runs, not lights.
+        brightness.copy_rect(0, 0, 899_999, 899_999, 100_000, 100_000);
+        assert_eq!(brightness.light(100_000, 100_000), 1);
+        assert_eq!(brightness.light(400_000, 100_005), 2);
+        assert_eq!(brightness.light(999_999, 999_999), 3);
+        assert!(brightness.runs() < 30);
+    }
+}
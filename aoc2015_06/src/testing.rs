@@ -0,0 +1,36 @@
+// Fixtures shared by the tests of the grid modules.
+
+use crate::instruction::Instruction;
+use crate::parse::parse_program;
+use crate::GridLike;
+
+pub const WIDTH: usize = 40;
+pub const HEIGHT: usize = 23;
+
+// Every shape and op code, a copy that reads what an earlier copy wrote, and
+// two full toggles that cancel out.
+pub const PROGRAM: &str = "turn on 0,0 through 30,20\n\
+                           toggle circle 20,12 radius 9\n\
+                           copy 0,0 through 15,10 to 14,9\n\
+                           dim outside 3,3 through 25,18 by 1\n\
+                           set line 0,22 to 39,0 to 5\n\
+                           copy 10,0 through 39,22 to 0,1\n\
+                           turn off 5,5 through 6,21\n\
+                           toggle 0,0 through 39,22\n\
+                           toggle 0,0 through 39,22\n";
+
+pub fn program() -> Vec<Instruction> {
+    parse_program(PROGRAM).unwrap()
+}
+
+// Every light, column by column.
+pub fn snapshot<G: GridLike>(grid: &G) -> Vec<usize> {
+    (0..grid.width()).flat_map(|x| (0..grid.height()).map(move |y| grid.light(x, y))).collect()
+}
+
+// The grid after applying the instructions in order.
+pub fn replay<G: GridLike>(program: &[Instruction], width: usize, height: usize) -> G {
+    let mut grid = G::new(width, height);
+    program.iter().for_each(|instruction| instruction.apply(&mut grid));
+    grid
+}